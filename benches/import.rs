@@ -0,0 +1,65 @@
+//! Importing todos through `TodoManager`: `add_todos` and `add_todo` inside
+//! `with_batched_writes` save once, plain `add_todo` saves on every row.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+
+use todo_cli::storage::Storage;
+use todo_cli::todo::{Priority, Todo, TodoManager};
+
+fn manager() -> TodoManager {
+    TodoManager::new(&Storage::in_memory().unwrap()).unwrap()
+}
+
+fn todos(count: usize) -> Vec<Todo> {
+    (0..count)
+        .map(|i| Todo::new(format!("imported {}", i), None, Priority::Medium, None, "alice".to_string()))
+        .collect()
+}
+
+fn import(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("import");
+    group.sample_size(10);
+
+    for count in [1_000, 10_000] {
+        group.bench_function(BenchmarkId::new("add_todos", count), |b| {
+            b.iter_batched(
+                || (manager(), todos(count)),
+                |(mut manager, todos)| runtime.block_on(manager.add_todos(todos)).unwrap(),
+                BatchSize::PerIteration,
+            )
+        });
+        group.bench_function(BenchmarkId::new("batched_add_todo", count), |b| {
+            b.iter_batched(
+                || (manager(), todos(count)),
+                |(mut manager, todos)| runtime.block_on(manager.with_batched_writes(|m| Box::pin(async move {
+                    for todo in todos {
+                        m.add_todo(todo).await?;
+                    }
+                    Ok(())
+                }))).unwrap(),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+
+    // Saving every row is quadratic; 10k takes minutes, so stop at 1k
+    for count in [100, 1_000] {
+        group.bench_function(BenchmarkId::new("unbatched_add_todo", count), |b| {
+            b.iter_batched(
+                || (manager(), todos(count)),
+                |(mut manager, todos)| runtime.block_on(async {
+                    for todo in todos {
+                        manager.add_todo(todo).await.unwrap();
+                    }
+                }),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, import);
+criterion_main!(benches);
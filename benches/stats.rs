@@ -0,0 +1,65 @@
+//! `StatsReport::compute` against the three separate scans it replaced, on
+//! lists the size of a busy shared install.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashSet;
+
+use todo_cli::reminder::elapsed_since;
+use todo_cli::stats::StatsReport;
+use todo_cli::todo::{Priority, Status, Todo};
+
+/// Half pending (some overdue), half completed over the last few months.
+fn todos(count: usize) -> Vec<Todo> {
+    let now = Utc::now();
+    (0..count)
+        .map(|i| {
+            let mut todo = Todo::new(format!("todo {}", i), None, Priority::Medium, None, "alice".to_string());
+            todo.created_at = now - Duration::hours((i % 2000) as i64 + 24);
+            if i % 2 == 0 {
+                todo.status = Status::Completed;
+                todo.completed_at = Some(todo.created_at + Duration::hours((i % 48) as i64));
+            } else if i % 3 == 0 {
+                todo.due_date = Some((Local::now() - Duration::days((i % 30) as i64)).naive_local());
+            }
+            todo
+        })
+        .collect()
+}
+
+/// The counters as they used to be built: one scan per figure, with the
+/// completed todos collected first.
+fn separate_passes(todos: &[Todo], now: DateTime<Local>) -> (usize, usize, usize, Option<f64>, HashSet<NaiveDate>) {
+    let pending = todos.iter().filter(|t| t.status == Status::Pending).count();
+    let completed: Vec<&Todo> = todos.iter().filter(|t| t.status == Status::Completed).collect();
+    let overdue = todos.iter().filter(|t| t.is_overdue(now)).count();
+    let avg_completion_hours = (!completed.is_empty()).then(|| {
+        let total_minutes: i64 = completed.iter()
+            .filter_map(|t| Some(elapsed_since(t.completed_time()?.with_timezone(&Local), t.created_at).num_minutes()))
+            .sum();
+        total_minutes as f64 / 60.0 / completed.len() as f64
+    });
+    let completion_days = completed.iter()
+        .filter_map(|t| t.completed_time())
+        .map(|done_at| done_at.with_timezone(&Local).date_naive())
+        .collect();
+    (pending, completed.len(), overdue, avg_completion_hours, completion_days)
+}
+
+fn stats(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stats");
+    let now = Local::now();
+    for count in [1_000, 10_000, 50_000] {
+        let todos = todos(count);
+        group.bench_with_input(BenchmarkId::new("single_pass", count), &todos, |b, todos| {
+            b.iter(|| StatsReport::compute(black_box(todos), now))
+        });
+        group.bench_with_input(BenchmarkId::new("separate_passes", count), &todos, |b, todos| {
+            b.iter(|| separate_passes(black_box(todos), now))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, stats);
+criterion_main!(benches);
@@ -1,12 +1,61 @@
-use anyhow::{Context, Result, anyhow};
-use bcrypt::{hash, verify, DEFAULT_COST};
+use bcrypt::{hash, verify, BcryptError, DEFAULT_COST};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use anyhow::Context;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::config::{Config, UserSettings};
 use crate::storage::Storage;
 
+const MIN_PASSWORD_LENGTH: usize = 6;
+
+/// Built-in list of the most widely used passwords, one per line.
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Username already exists")]
+    UsernameTaken,
+    #[error("Email already exists")]
+    EmailTaken,
+    #[error("Username cannot be empty")]
+    EmptyUsername,
+    #[error("Invalid email address")]
+    InvalidEmail,
+    #[error("Password must be at least {0} characters long")]
+    PasswordTooShort(usize),
+    #[error("That password is on a list of common passwords; please choose another")]
+    CommonPassword,
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+    /// Nothing to log in to yet.
+    #[error("No accounts yet. Create one with: todo register")]
+    NoAccounts,
+    #[error("Not logged in. Log in with: todo login, or create an account with: todo register")]
+    NotAuthenticated,
+    #[error("Your session expired, please log in again with: todo login")]
+    SessionExpired,
+    #[error("Your session timed out after inactivity, please log in again with: todo login")]
+    SessionIdle,
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Only administrators can {0}")]
+    AdminOnly(&'static str),
+    #[error("Failed to hash password")]
+    Hash(#[source] BcryptError),
+    #[error("Failed to verify password")]
+    Verify(#[source] BcryptError),
+    /// Reading or writing the user and session files failed.
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+pub type Result<T, E = AuthError> = std::result::Result<T, E>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: String,
@@ -15,6 +64,23 @@ pub struct User {
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
     pub last_login: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub settings: UserSettings,
+    /// May inspect other users' todos with `--user`. The first account
+    /// registered on an install gets this.
+    #[serde(default)]
+    pub is_admin: bool,
+    /// Wrong passwords typed for this account since it last logged in with
+    /// `login`, reported and reset when it next does.
+    #[serde(default)]
+    pub failed_logins: FailedLogins,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailedLogins {
+    pub count: u32,
+    pub last_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,16 +88,28 @@ pub struct Session {
     pub user_id: String,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    /// Last authenticated command. Sessions saved before this was tracked
+    /// count as active when first loaded.
+    #[serde(default = "Utc::now")]
+    pub last_activity: DateTime<Utc>,
+    /// Project picked with `todo use`; new todos and lists default to it.
+    #[serde(default)]
+    pub context: Option<String>,
 }
 
 pub struct AuthManager {
     storage: Storage,
     current_session: Option<Session>,
     users: HashMap<String, User>,
+    /// Sessions unused for longer than this are rejected; `None` disables the check.
+    idle_timeout: Option<chrono::Duration>,
+    reject_common_passwords: bool,
+    /// Wordlist used instead of the built-in one.
+    common_passwords_file: Option<PathBuf>,
 }
 
 impl AuthManager {
-    pub fn new(storage: &Storage) -> Result<Self> {
+    pub fn new(storage: &Storage, config: &Config) -> Result<Self> {
         let users = storage.load_users()?;
         let current_session = storage.load_session()?;
         
@@ -39,36 +117,45 @@ impl AuthManager {
             storage: storage.clone(),
             current_session,
             users,
+            idle_timeout: config.idle_timeout_minutes
+                .filter(|&minutes| minutes > 0)
+                .map(chrono::Duration::minutes),
+            reject_common_passwords: config.reject_common_passwords,
+            common_passwords_file: config.common_passwords_file.clone(),
         })
     }
     
     pub async fn register(&mut self, username: &str, email: &str, password: &str) -> Result<User> {
         // Check if username already exists
         if self.users.values().any(|u| u.username == username) {
-            return Err(anyhow!("Username already exists"));
+            return Err(AuthError::UsernameTaken);
         }
         
         // Check if email already exists
         if self.users.values().any(|u| u.email == email) {
-            return Err(anyhow!("Email already exists"));
+            return Err(AuthError::EmailTaken);
         }
         
         // Validate input
         if username.trim().is_empty() {
-            return Err(anyhow!("Username cannot be empty"));
+            return Err(AuthError::EmptyUsername);
         }
         
         if email.trim().is_empty() || !email.contains('@') {
-            return Err(anyhow!("Invalid email address"));
+            return Err(AuthError::InvalidEmail);
+        }
+        
+        if password.len() < MIN_PASSWORD_LENGTH {
+            return Err(AuthError::PasswordTooShort(MIN_PASSWORD_LENGTH));
         }
         
-        if password.len() < 6 {
-            return Err(anyhow!("Password must be at least 6 characters long"));
+        if self.is_common_password(password)? {
+            return Err(AuthError::CommonPassword);
         }
         
         // Hash password
         let password_hash = hash(password, DEFAULT_COST)
-            .context("Failed to hash password")?;
+            .map_err(AuthError::Hash)?;
         
         // Create user
         let user = User {
@@ -78,6 +165,9 @@ impl AuthManager {
             password_hash,
             created_at: Utc::now(),
             last_login: None,
+            settings: UserSettings::default(),
+            is_admin: self.users.is_empty(),
+            failed_logins: FailedLogins::default(),
         };
         
         // Store user
@@ -87,18 +177,66 @@ impl AuthManager {
         Ok(user)
     }
     
-    pub async fn login(&mut self, username: &str, password: &str) -> Result<User> {
-        let user = self.users.values()
+    /// Signs in with a password. Alongside the user, returns the failed
+    /// attempts on the account since its last login, which are then reset.
+    /// A wrong password for an existing account adds to them.
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<(User, FailedLogins)> {
+        let user = self.users.values_mut()
             .find(|u| u.username == username)
-            .ok_or_else(|| anyhow!("Invalid username or password"))?;
+            .ok_or(AuthError::InvalidCredentials)?;
         
         if !verify(password, &user.password_hash)
-            .context("Failed to verify password")? {
-            return Err(anyhow!("Invalid username or password"));
+            .map_err(AuthError::Verify)? {
+            user.failed_logins.count += 1;
+            user.failed_logins.last_at = Some(Utc::now());
+            self.storage.save_users(&self.users)?;
+            return Err(AuthError::InvalidCredentials);
+        }
+        
+        let failed = std::mem::take(&mut user.failed_logins);
+        let user_id = user.id.clone();
+        self.start_session(&user_id).map(|user| (user, failed))
+    }
+    
+    /// Whether `password` appears, ignoring case, in the configured wordlist
+    /// or the built-in one. Always `false` when the check is turned off.
+    fn is_common_password(&self, password: &str) -> Result<bool> {
+        if !self.reject_common_passwords {
+            return Ok(false);
         }
         
+        let custom;
+        let list = match &self.common_passwords_file {
+            Some(path) => {
+                custom = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read password list {}", path.display()))?;
+                custom.as_str()
+            },
+            None => COMMON_PASSWORDS,
+        };
+        
+        let words: HashSet<String> = list.lines()
+            .map(|line| line.trim().to_lowercase())
+            .filter(|line| !line.is_empty())
+            .collect();
+        Ok(words.contains(&password.to_lowercase()))
+    }
+    
+    /// Signs in the only account without a password. Returns `None`, doing
+    /// nothing, unless exactly one user exists.
+    pub fn auto_login(&mut self) -> Result<Option<User>> {
+        if self.users.len() != 1 {
+            return Ok(None);
+        }
+        let user_id = self.users.keys().next().cloned().ok_or(AuthError::UserNotFound)?;
+        self.start_session(&user_id).map(Some)
+    }
+    
+    fn start_session(&mut self, user_id: &str) -> Result<User> {
         // Update last login
-        let mut updated_user = user.clone();
+        let mut updated_user = self.users.get(user_id)
+            .cloned()
+            .ok_or(AuthError::UserNotFound)?;
         updated_user.last_login = Some(Utc::now());
         self.users.insert(updated_user.id.clone(), updated_user.clone());
         self.storage.save_users(&self.users)?;
@@ -108,6 +246,8 @@ impl AuthManager {
             user_id: updated_user.id.clone(),
             created_at: Utc::now(),
             expires_at: Utc::now() + chrono::Duration::days(7), // Session expires in 7 days
+            last_activity: Utc::now(),
+            context: None,
         };
         
         self.current_session = Some(session.clone());
@@ -122,24 +262,58 @@ impl AuthManager {
         Ok(())
     }
     
+    pub fn has_users(&self) -> bool {
+        !self.users.is_empty()
+    }
+    
     pub fn is_authenticated(&self) -> bool {
         if let Some(ref session) = self.current_session {
-            session.expires_at > Utc::now()
+            session.expires_at > Utc::now() && !self.is_idle(session)
         } else {
             false
         }
     }
     
+    fn is_idle(&self, session: &Session) -> bool {
+        self.idle_timeout
+            .is_some_and(|timeout| Utc::now() - session.last_activity > timeout)
+    }
+    
+    /// Records activity on the current session so the idle timeout restarts.
+    pub fn touch_session(&mut self) -> Result<()> {
+        if let Some(ref mut session) = self.current_session {
+            session.last_activity = Utc::now();
+            self.storage.save_session(session)?;
+        }
+        Ok(())
+    }
+    
+    pub fn current_context(&self) -> Option<&str> {
+        self.current_session.as_ref()?.context.as_deref()
+    }
+    
+    pub fn set_context(&mut self, context: Option<String>) -> Result<()> {
+        let session = self.current_session.as_mut()
+            .ok_or(AuthError::NotAuthenticated)?;
+        session.context = context;
+        self.storage.save_session(session)?;
+        Ok(())
+    }
+    
     pub fn get_current_user(&self) -> Result<User> {
         let session = self.current_session.as_ref()
-            .ok_or_else(|| anyhow!("Not authenticated"))?;
+            .ok_or(AuthError::NotAuthenticated)?;
         
         if session.expires_at <= Utc::now() {
-            return Err(anyhow!("Session expired"));
+            return Err(AuthError::SessionExpired);
+        }
+        
+        if self.is_idle(session) {
+            return Err(AuthError::SessionIdle);
         }
         
         let user = self.users.get(&session.user_id)
-            .ok_or_else(|| anyhow!("User not found"))?;
+            .ok_or(AuthError::UserNotFound)?;
         
         Ok(user.clone())
     }
@@ -147,4 +321,25 @@ impl AuthManager {
     pub fn get_user_by_id(&self, user_id: &str) -> Option<&User> {
         self.users.get(user_id)
     }
-}
\ No newline at end of file
+    
+    pub fn users(&self) -> impl Iterator<Item = &User> {
+        self.users.values()
+    }
+    
+    pub fn find_by_username(&self, username: &str) -> Option<&User> {
+        self.users.values().find(|u| u.username == username)
+    }
+    
+    /// Emails compare ignoring case and surrounding spaces.
+    pub fn find_by_email(&self, email: &str) -> Option<&User> {
+        let email = email.trim();
+        self.users.values().find(|u| u.email.eq_ignore_ascii_case(email))
+    }
+    
+    pub fn update_settings(&mut self, user_id: &str, settings: UserSettings) -> Result<()> {
+        let user = self.users.get_mut(user_id)
+            .ok_or(AuthError::UserNotFound)?;
+        user.settings = settings;
+        Ok(self.storage.save_users(&self.users)?)
+    }
+}
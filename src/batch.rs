@@ -0,0 +1,165 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::dates::{DateOrder, Deferral};
+use crate::import::ImportRecord;
+use crate::todo::{self, Priority, SortKey, Todo, TodoError, TodoManager};
+
+/// One line of `todo batch` input, picked by its `op` field. Todos are
+/// referenced the way the CLI takes them: an id, an id prefix, a list
+/// position or `last`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Op {
+    /// Same fields as an `import` record.
+    Add(ImportRecord),
+    Complete {
+        id: String,
+        #[serde(default)]
+        note: Option<String>,
+    },
+    Reopen {
+        id: String,
+    },
+    Delete {
+        id: String,
+    },
+    /// `by` takes what `defer --by` does.
+    Defer {
+        id: String,
+        by: String,
+    },
+    Priority {
+        id: String,
+        level: String,
+    },
+    Get {
+        id: String,
+    },
+    /// The user's live todos as the default `list` shows them, in the
+    /// order list positions refer to.
+    List,
+}
+
+/// The answer to one input line, printed as one line of JSON.
+#[derive(Debug, Serialize)]
+pub struct Reply {
+    /// 1-based line number of the request.
+    pub line: usize,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todos: Option<Vec<Todo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Who the batch runs as and the settings single commands would use.
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub user_id: String,
+    /// The project picked with `use`, for resolving list positions.
+    pub project: Option<String>,
+    pub sort: SortKey,
+    pub date_order: DateOrder,
+    pub default_priority: Priority,
+    pub skip_weekends: bool,
+}
+
+/// Runs every line against `manager` with writes batched, so however many
+/// lines change todos the data is saved once, after the last. A line that
+/// fails gets an error reply and the rest carry on. Blank lines are skipped.
+pub async fn run(manager: &mut TodoManager, context: Context, lines: Vec<String>) -> todo::Result<Vec<Reply>> {
+    manager.with_batched_writes(|manager| Box::pin(async move {
+        let mut replies = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match serde_json::from_str::<Op>(line) {
+                Ok(op) => execute(manager, &context, op).await,
+                Err(e) => Err(format!("Invalid request: {}", e)),
+            };
+            replies.push(match reply {
+                Ok((todo, todos)) => Reply { line: index + 1, ok: true, todo, todos, error: None },
+                Err(error) => Reply { line: index + 1, ok: false, todo: None, todos: None, error: Some(error) },
+            });
+        }
+        Ok(replies)
+    })).await
+}
+
+type Outcome = Result<(Option<Todo>, Option<Vec<Todo>>), String>;
+
+/// A todo the user can see, which is what every single-todo op answers with.
+async fn fetch(manager: &TodoManager, user_id: &str, id: &str) -> Outcome {
+    match manager.get_todo(id).await {
+        Ok(todo) if todo.is_visible_to(user_id) => Ok((Some(todo), None)),
+        _ => Err(TodoError::NotFound.to_string()),
+    }
+}
+
+async fn execute(manager: &mut TodoManager, context: &Context, op: Op) -> Outcome {
+    let user_id = context.user_id.as_str();
+    let resolve = |manager: &TodoManager, reference: &str| {
+        manager.resolve_reference(reference, user_id, context.project.as_deref(), context.sort)
+            .map_err(|e| e.to_string())
+    };
+
+    match op {
+        Op::Add(record) => {
+            let mut todo = record.into_todo(user_id, context.date_order, &context.default_priority)
+                .map_err(|e| e.to_string())?;
+            if context.skip_weekends {
+                todo.due_date = todo.due_date.map(crate::dates::skip_weekend);
+            }
+            manager.add_todo(todo.clone()).await.map_err(|e| e.to_string())?;
+            Ok((Some(todo), None))
+        },
+        Op::Complete { id, note } => {
+            let id = resolve(manager, &id)?;
+            if !manager.complete_todo(&id, user_id, note).await.map_err(|e| e.to_string())? {
+                return Err(TodoError::AlreadyCompleted.to_string());
+            }
+            fetch(manager, user_id, &id).await
+        },
+        Op::Reopen { id } => {
+            let id = resolve(manager, &id)?;
+            manager.reopen_todo(&id, user_id).await.map_err(|e| e.to_string())?;
+            fetch(manager, user_id, &id).await
+        },
+        Op::Delete { id } => {
+            let id = resolve(manager, &id)?;
+            let todo = fetch(manager, user_id, &id).await?;
+            manager.delete_todo(&id, user_id).await.map_err(|e| e.to_string())?;
+            Ok(todo)
+        },
+        Op::Defer { id, by } => {
+            let id = resolve(manager, &id)?;
+            let today = Local::now().date_naive();
+            let deferral = Deferral::parse(&by, today).map_err(|e| e.to_string())?;
+            let todo = manager.defer_todo(&id, user_id, deferral, today, context.skip_weekends).await
+                .map_err(|e| e.to_string())?;
+            Ok((Some(todo), None))
+        },
+        Op::Priority { id, level } => {
+            let id = resolve(manager, &id)?;
+            let priority = Priority::from_string(&level).map_err(|e| e.to_string())?;
+            let (todo, _) = manager.set_priority(&id, user_id, priority).await.map_err(|e| e.to_string())?;
+            Ok((Some(todo), None))
+        },
+        Op::Get { id } => {
+            let id = resolve(manager, &id)?;
+            fetch(manager, user_id, &id).await
+        },
+        Op::List => {
+            // The order positions resolve against, so "2" is the second reply entry
+            let listing = manager.listing(user_id, context.project.as_deref(), context.sort)
+                .into_iter()
+                .cloned()
+                .collect();
+            Ok((None, Some(listing)))
+        },
+    }
+}
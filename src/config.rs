@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::dates::{self, DateOrder, WeekStart};
+use crate::todo::{Priority, SortKey};
+
+/// Global application settings, read from `config.json` in the config directory.
+/// Every field has a default so a missing or partial file is always valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Ordering used by every list view unless a command passes `--sort`.
+    pub default_sort: SortKey,
+    /// How `list` prints each todo unless `--oneline` or `--detailed` is given.
+    pub list_format: ListFormat,
+    /// Which actions ask "are you sure?" before running.
+    pub confirmations: Confirmations,
+    /// Replacement wording for reminder messages, keyed by reminder kind.
+    pub reminder_templates: HashMap<String, String>,
+    /// Use plain-text markers everywhere instead of emoji (same as `--ascii`).
+    pub ascii: bool,
+    /// Per-symbol overrides, keyed by the field names in `icons::Icons`.
+    pub emojis: HashMap<String, String>,
+    /// Whether year-last dates are day-first (`dmy`) or month-first (`mdy`).
+    pub date_order: DateOrder,
+    /// First column of `calendar` and start of the week for the leaderboard
+    /// and `completed week`.
+    pub week_start: WeekStart,
+    /// `strftime` pattern for timestamps shown in the terminal.
+    pub date_format: String,
+    /// Characters of each todo id to show. Grows on its own when shorter ids
+    /// would be ambiguous.
+    pub id_display_length: usize,
+    /// Read `!priority`, `#tag`, `@project` and `due:<day>` out of titles given
+    /// to `add`, as `add --quick` does.
+    pub quick_add: bool,
+    /// Refuse to add todos without a due date.
+    pub require_due_date: bool,
+    /// Move due dates that would land on a Saturday or Sunday to the Monday
+    /// after, when adding, deferring and repeating todos.
+    pub skip_weekends: bool,
+    /// Flag Medium and Low todos due within this many hours as "due soon";
+    /// 0 turns the hint off.
+    pub urgency_hint_hours: i64,
+    /// Priority preselected when adding a todo interactively.
+    pub default_priority: Priority,
+    /// When reminders start nagging.
+    pub reminders: ReminderConfig,
+    /// Reminders listed at login before the rest are summarized; 0 shows all.
+    pub max_reminders_shown: usize,
+    /// Enables `todo leaderboard`, which shows every user's weekly completion count.
+    pub leaderboard: bool,
+    /// Sign in automatically when exactly one account exists. Anyone with
+    /// access to this OS account can then use the todo account.
+    pub single_user_auto_login: bool,
+    /// When a session has expired or timed out, ask for the password right
+    /// away instead of failing the command. Only on a terminal.
+    pub login_on_expiry: bool,
+    /// After `login`, say how many wrong passwords were tried on the account
+    /// since its last login, and when the last one was.
+    pub failed_login_notice: bool,
+    /// Let `forgot-username` print the username for an email. When off it
+    /// gives the same answer whether or not an account exists.
+    pub reveal_username_by_email: bool,
+    /// Keep todos in `todos.json.gz` instead of plain `todos.json`.
+    pub compress_storage: bool,
+    /// Write every change as it's made. When off, changes are kept in memory
+    /// until "Save" in the interactive menu or the program exits.
+    pub auto_save: bool,
+    /// Order of the pending and completed sections in `todos.md`.
+    pub markdown_sort: SortKey,
+    /// Refuse passwords found on a list of common ones when registering.
+    pub reject_common_passwords: bool,
+    /// Newline-separated wordlist to check instead of the built-in list.
+    pub common_passwords_file: Option<PathBuf>,
+    /// Log out sessions that go unused for this many minutes. Off when unset.
+    pub idle_timeout_minutes: Option<i64>,
+    /// Archive a recurring todo when it is completed, so only its next
+    /// occurrence shows in lists.
+    pub archive_completed_recurrences: bool,
+    /// Complete a todo as soon as `toggle-subtask` ticks off its last open
+    /// subtask.
+    pub auto_complete_parent: bool,
+    /// Most pending, unarchived todos one user may own. Unlimited when unset.
+    pub max_todos_per_user: Option<usize>,
+    /// How alike two titles must be, from 0.0 to 1.0, for `add
+    /// --check-duplicates` to call them duplicates.
+    pub duplicate_threshold: f64,
+    /// Where to announce added, completed and deleted todos.
+    pub webhooks: WebhookConfig,
+}
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_sort: SortKey::default(),
+            list_format: ListFormat::default(),
+            confirmations: Confirmations::default(),
+            reminder_templates: HashMap::new(),
+            ascii: false,
+            emojis: HashMap::new(),
+            date_order: DateOrder::default(),
+            week_start: WeekStart::default(),
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            id_display_length: 8,
+            quick_add: false,
+            require_due_date: false,
+            skip_weekends: false,
+            urgency_hint_hours: 24,
+            default_priority: Priority::Medium,
+            reminders: ReminderConfig::default(),
+            max_reminders_shown: 5,
+            leaderboard: false,
+            single_user_auto_login: false,
+            login_on_expiry: false,
+            failed_login_notice: true,
+            reveal_username_by_email: true,
+            compress_storage: false,
+            auto_save: true,
+            markdown_sort: SortKey::Due,
+            reject_common_passwords: true,
+            common_passwords_file: None,
+            idle_timeout_minutes: None,
+            archive_completed_recurrences: false,
+            auto_complete_parent: false,
+            max_todos_per_user: None,
+            duplicate_threshold: 0.9,
+            webhooks: WebhookConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// The global config with the user's own preferences layered on top.
+    pub fn with_user_settings(&self, settings: &UserSettings) -> Config {
+        let mut config = self.clone();
+        if let Some(priority) = &settings.default_priority {
+            config.default_priority = priority.clone();
+        }
+        if let Some(order) = settings.date_order {
+            config.date_order = order;
+        }
+        if let Some(format) = &settings.date_format {
+            config.date_format = format.clone();
+        }
+        if let Some(days) = settings.upcoming_days {
+            config.reminders.upcoming_days = days;
+        }
+        if let Some(days) = settings.stale_days {
+            config.reminders.stale_days = days;
+        }
+        config
+    }
+    
+    /// `id_display_length` kept between 4 and a full UUID.
+    pub fn id_length(&self) -> usize {
+        self.id_display_length.clamp(4, 36)
+    }
+    
+    /// `date_format`, or the built-in pattern if the configured one is malformed.
+    pub fn display_format(&self) -> &str {
+        if dates::is_valid_format(&self.date_format) {
+            &self.date_format
+        } else {
+            DEFAULT_DATE_FORMAT
+        }
+    }
+}
+
+/// How much of each todo `list` shows.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListFormat {
+    /// Every field, over several lines.
+    #[default]
+    Detailed,
+    /// Id, priority, status, title and due date on one line.
+    Oneline,
+}
+
+/// Thresholds for the reminder checks, in days.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderConfig {
+    /// Todos due within this many days get a "due soon" reminder.
+    pub upcoming_days: i64,
+    /// Undated todos pending longer than this many days get a nudge.
+    pub stale_days: i64,
+    /// Per-priority warning points that replace the generic due-soon checks.
+    pub schedule: ReminderSchedule,
+    /// How reminders are ranked against each other.
+    pub urgency: UrgencyWeights,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            upcoming_days: 7,
+            stale_days: 7,
+            schedule: ReminderSchedule::default(),
+            urgency: UrgencyWeights::default(),
+        }
+    }
+}
+
+/// Hours before the due date at which to warn, per priority. A todo only
+/// reports the tightest window it has entered, so windows never overlap. An
+/// empty list keeps the generic checks for that priority.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderSchedule {
+    pub high: Vec<i64>,
+    pub medium: Vec<i64>,
+    pub low: Vec<i64>,
+}
+
+impl Default for ReminderSchedule {
+    fn default() -> Self {
+        Self {
+            high: vec![72, 24, 3],
+            medium: Vec::new(),
+            low: vec![24],
+        }
+    }
+}
+
+impl ReminderSchedule {
+    pub fn for_priority(&self, priority: &Priority) -> &[i64] {
+        match priority {
+            Priority::High => &self.high,
+            Priority::Medium => &self.medium,
+            Priority::Low => &self.low,
+        }
+    }
+}
+
+/// An HTTP endpoint that gets a POST for every added, completed or deleted
+/// todo. Off while `url` is unset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: Option<String>,
+    /// Give up on a request after this many seconds.
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            timeout_secs: 5,
+        }
+    }
+}
+
+/// Weights for `reminder::urgency_score`, which orders reminders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UrgencyWeights {
+    /// Multiplies the priority term: 0 for Low, 0.5 for Medium, 1 for High.
+    pub priority: f64,
+    /// Multiplies the due-date term, which grows from 0 toward 1 as the due
+    /// date nears and keeps growing once it has passed.
+    pub due: f64,
+    /// How much the due-date term grows per day overdue.
+    pub overdue_per_day: f64,
+}
+
+impl Default for UrgencyWeights {
+    fn default() -> Self {
+        Self {
+            priority: 1.0,
+            due: 2.0,
+            overdue_per_day: 0.25,
+        }
+    }
+}
+
+/// One user's preferences, stored with their account. Unset fields fall back
+/// to the global config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserSettings {
+    pub default_priority: Option<Priority>,
+    pub date_order: Option<DateOrder>,
+    pub date_format: Option<String>,
+    pub upcoming_days: Option<i64>,
+    pub stale_days: Option<i64>,
+}
+
+/// Per-action confirmation prompts. Prompts are never shown when stdin is
+/// not a terminal, so scripts are unaffected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Confirmations {
+    pub delete: bool,
+    pub complete: bool,
+    /// Completing a repeating todo that `archive_completed_recurrences`
+    /// will archive, and `prune --archive`.
+    pub archive: bool,
+}
+
+impl Default for Confirmations {
+    fn default() -> Self {
+        Self {
+            delete: true,
+            complete: false,
+            archive: false,
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::icons::Icons;
+use crate::todo::{self, sort_todos, Priority, SortKey, Status, Todo, TodoManager};
+
+/// What keystrokes currently mean.
+enum Mode {
+    Browse,
+    /// Typing a search; the list narrows as the query changes.
+    Search,
+    /// Waiting for y/n before deleting the selected todo.
+    ConfirmDelete,
+}
+
+/// Full-screen todo list driven by single keys. Every change goes through
+/// `TodoManager`, so it is saved immediately, exactly as from the CLI.
+struct Dashboard<'a> {
+    manager: &'a mut TodoManager,
+    user_id: String,
+    sort: SortKey,
+    icons: &'a Icons,
+    /// Minimum id characters to show (`id_display_length`).
+    id_length: usize,
+    todos: Vec<Todo>,
+    query: String,
+    /// Hide todos that can't be worked on now (`a` toggles).
+    actionable: bool,
+    state: ListState,
+    mode: Mode,
+    message: String,
+}
+
+/// Takes over the terminal until the user quits, restoring it afterwards
+/// even if something fails.
+pub async fn run(manager: &mut TodoManager, user_id: &str, sort: SortKey, icons: &Icons, id_length: usize) -> Result<()> {
+    let mut dashboard = Dashboard {
+        manager,
+        user_id: user_id.to_string(),
+        sort,
+        icons,
+        id_length,
+        todos: Vec::new(),
+        query: String::new(),
+        actionable: false,
+        state: ListState::default(),
+        mode: Mode::Browse,
+        message: String::new(),
+    };
+    dashboard.refresh().await?;
+
+    let mut terminal = ratatui::init();
+    let result = dashboard.event_loop(&mut terminal).await;
+    ratatui::restore();
+    result
+}
+
+impl Dashboard<'_> {
+    async fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code).await? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Reloads the visible todos, keeping the selection in range.
+    async fn refresh(&mut self) -> Result<()> {
+        let todos = self.manager.get_user_todos(&self.user_id).await?;
+        let query = self.query.to_lowercase();
+        let now = Local::now();
+        let mut visible: Vec<&Todo> = todos.iter()
+            .filter(|t| !t.archived && !t.someday)
+            .filter(|t| !self.actionable || t.is_actionable(now))
+            .filter(|t| query.is_empty() || matches_query(t, &query))
+            .collect();
+        sort_todos(&mut visible, self.sort);
+        self.todos = visible.into_iter().cloned().collect();
+
+        let selected = match self.state.selected() {
+            _ if self.todos.is_empty() => None,
+            Some(i) => Some(i.min(self.todos.len() - 1)),
+            None => Some(0),
+        };
+        self.state.select(selected);
+        Ok(())
+    }
+
+    fn selected(&self) -> Option<&Todo> {
+        self.state.selected().and_then(|i| self.todos.get(i))
+    }
+
+    /// Returns `false` when the user asked to quit.
+    async fn handle_key(&mut self, code: KeyCode) -> Result<bool> {
+        match self.mode {
+            Mode::Browse => return self.handle_browse_key(code).await,
+            Mode::Search => match code {
+                KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.refresh().await?;
+                },
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.refresh().await?;
+                },
+                _ => {},
+            },
+            Mode::ConfirmDelete => {
+                self.mode = Mode::Browse;
+                if code == KeyCode::Char('y') {
+                    if let Some(todo) = self.selected().cloned() {
+                        self.manager.delete_todo(&todo.id, &self.user_id).await?;
+                        self.message = format!("Deleted '{}'", todo.title);
+                        self.refresh().await?;
+                    }
+                } else {
+                    self.message = "Delete cancelled".to_string();
+                }
+            },
+        }
+        Ok(true)
+    }
+
+    async fn handle_browse_key(&mut self, code: KeyCode) -> Result<bool> {
+        self.message.clear();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+            KeyCode::Char('/') => self.mode = Mode::Search,
+            KeyCode::Char('a') => {
+                self.actionable = !self.actionable;
+                self.refresh().await?;
+            },
+            KeyCode::Char('c') => {
+                if let Some(todo) = self.selected().cloned() {
+                    if self.manager.complete_todo(&todo.id, &self.user_id, None).await? {
+                        self.message = format!("Completed '{}'", todo.title);
+                        self.refresh().await?;
+                    } else {
+                        self.message = format!("'{}' is already completed", todo.title);
+                    }
+                }
+            },
+            KeyCode::Char('d') => {
+                if let Some(todo) = self.selected() {
+                    if todo.user_id == self.user_id {
+                        self.mode = Mode::ConfirmDelete;
+                    } else {
+                        self.message = "Only the owner can delete this todo".to_string();
+                    }
+                }
+            },
+            KeyCode::Char('e') => {
+                if let Some(todo) = self.selected() {
+                    self.message = format!("Editing isn't available here yet; run: todo edit {}", todo.id);
+                }
+            },
+            _ => {},
+        }
+        Ok(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list_area, footer_area] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let now = Local::now();
+        let id_length = self.manager.id_display_length(self.id_length);
+        let items: Vec<ListItem> = self.todos.iter()
+            .map(|todo| ListItem::new(self.todo_line(todo, now, id_length)))
+            .collect();
+
+        let heading = if self.actionable { "Actionable todos" } else { "Todos" };
+        let title = if self.query.is_empty() {
+            format!(" {} ({}) ", heading, self.todos.len())
+        } else {
+            format!(" {} matching '{}' ({}) ", heading, self.query, self.todos.len())
+        };
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let footer = match self.mode {
+            Mode::Search => Line::from(format!("/{}", self.query)),
+            Mode::ConfirmDelete => Line::from(format!(
+                "Delete '{}'? (y/n)",
+                self.selected().map(|t| t.title.as_str()).unwrap_or("")
+            )).style(Style::default().fg(Color::Red)),
+            Mode::Browse if !self.message.is_empty() => Line::from(self.message.as_str()),
+            Mode::Browse => Line::from("↑/↓ move  c complete  d delete  e edit  / search  a actionable  q quit")
+                .style(Style::default().fg(Color::DarkGray)),
+        };
+        frame.render_widget(Paragraph::new(footer), footer_area);
+    }
+
+    fn todo_line(&self, todo: &Todo, now: DateTime<Local>, id_length: usize) -> Line<'static> {
+        let status = match todo.status {
+            Status::Pending => &self.icons.status_pending,
+            Status::Completed => &self.icons.status_completed,
+        };
+        let priority = match todo.priority {
+            Priority::Low => &self.icons.priority_low,
+            Priority::Medium => &self.icons.priority_medium,
+            Priority::High => &self.icons.priority_high,
+        };
+
+        let mut spans = vec![
+            Span::raw(format!("{} {} ", status, priority)),
+            Span::styled(todo::abbreviate(&todo.id, id_length).to_string(), Style::default().fg(Color::DarkGray)),
+            Span::raw(" "),
+            Span::styled(todo.title.clone(), Style::default().add_modifier(Modifier::BOLD)),
+        ];
+        if let Some(ref waiting) = todo.waiting {
+            let text = match waiting.on {
+                Some(ref on) => format!("  {} waiting on {}", self.icons.waiting, on),
+                None => format!("  {} waiting", self.icons.waiting),
+            };
+            spans.push(Span::styled(text, Style::default().fg(Color::Magenta)));
+        }
+        if let Some(due) = todo.due_date {
+            let style = if todo.is_overdue(now) {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default().fg(Color::Blue)
+            };
+            spans.push(Span::styled(format!("  due {}", due.format("%Y-%m-%d")), style));
+        }
+        Line::from(spans)
+    }
+}
+
+fn matches_query(todo: &Todo, query: &str) -> bool {
+    todo.title.to_lowercase().contains(query)
+        || todo.description.as_deref().is_some_and(|d| d.to_lowercase().contains(query))
+}
@@ -0,0 +1,392 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Input that doesn't read as a date, time, zone or amount. The message says
+/// what was expected.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct ParseError(String);
+
+pub type Result<T, E = ParseError> = std::result::Result<T, E>;
+
+/// How to read dates written with the year last, e.g. `01-05-2024`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DateOrder {
+    /// Day first: `01-05-2024` is 1 May.
+    #[default]
+    Dmy,
+    /// Month first: `01-05-2024` is 5 January.
+    Mdy,
+}
+
+impl DateOrder {
+    pub fn from_string(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "dmy" => Ok(DateOrder::Dmy),
+            "mdy" => Ok(DateOrder::Mdy),
+            _ => Err(ParseError(format!("Invalid date order '{}'. Use dmy or mdy", s.trim()))),
+        }
+    }
+}
+
+/// The day calendar rows and "this week" windows begin on.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    pub fn first_day(self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+
+    pub fn last_day(self) -> Weekday {
+        self.first_day().pred()
+    }
+
+    /// The most recent `first_day` on or before `date`. Every weekly view
+    /// goes through this, so they all agree on where a week begins.
+    pub fn start_of_week(self, date: NaiveDate) -> NaiveDate {
+        let offset = match self {
+            WeekStart::Monday => date.weekday().num_days_from_monday(),
+            WeekStart::Sunday => date.weekday().num_days_from_sunday(),
+        };
+        date - Duration::days(offset as i64)
+    }
+}
+
+/// A window ending now, for `todo completed`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Today,
+    /// Since the start of the week, per `week_start`.
+    Week,
+    Month,
+}
+
+impl Period {
+    pub fn from_string(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "today" | "day" => Ok(Period::Today),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => Err(ParseError(format!("Invalid period '{}'. Use today, week or month", s.trim()))),
+        }
+    }
+
+    /// The first day of the period that contains `today`.
+    pub fn first_day(self, today: NaiveDate, week_start: WeekStart) -> NaiveDate {
+        match self {
+            Period::Today => today,
+            Period::Week => week_start.start_of_week(today),
+            Period::Month => today.with_day(1).unwrap_or(today),
+        }
+    }
+}
+
+/// Local midnight at the start of `date`, as UTC, for comparing with stored
+/// timestamps.
+pub fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Parses a user-typed due date into local time, at the end of the day
+/// unless a time follows.
+///
+/// Accepts `YYYY-MM-DD` and, with the year last, `DD-MM-YYYY` or `MM-DD-YYYY`
+/// depending on `order`. `/` and `.` work as separators too. A 24-hour
+/// `HH:MM` may follow after a space, e.g. `2024-05-01 09:00`.
+pub fn parse_due_date(input: &str, order: DateOrder) -> Result<NaiveDateTime> {
+    let (day, time) = match input.trim().split_once(' ') {
+        Some((day, time)) => {
+            let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| ParseError(format!("Invalid time '{}'. Use 24-hour HH:MM, e.g. 09:00", time.trim())))?;
+            (day, time)
+        },
+        None => (input.trim(), end_of_day()),
+    };
+    let normalized = day.replace(['/', '.'], "-");
+
+    let year_last = match order {
+        DateOrder::Dmy => "%d-%m-%Y",
+        DateOrder::Mdy => "%m-%d-%Y",
+    };
+
+    ["%Y-%m-%d", year_last].iter()
+        .find_map(|format| NaiveDate::parse_from_str(&normalized, format).ok())
+        .map(|date| date.and_time(time))
+        .ok_or_else(|| ParseError(format!(
+            "Invalid due date '{}'. Use YYYY-MM-DD, YYYY/MM/DD or {}",
+            day,
+            match order {
+                DateOrder::Dmy => "DD-MM-YYYY",
+                DateOrder::Mdy => "MM-DD-YYYY",
+            }
+        )))
+}
+
+/// Parses a day named the way people say it: `today`, `tomorrow`, a weekday
+/// such as `monday` or `next fri` (always the next one after today), or any
+/// date `parse_due_date` accepts.
+pub fn parse_day(input: &str, order: DateOrder, today: NaiveDate) -> Result<NaiveDate> {
+    let lowered = input.trim().to_lowercase();
+    let word = lowered.strip_prefix("next ").unwrap_or(&lowered).trim();
+    match word {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Duration::days(1)),
+        _ => {},
+    }
+    if let Ok(weekday) = word.parse::<Weekday>() {
+        let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return Ok(today + Duration::days(ahead as i64));
+    }
+    parse_due_date(input, order)
+        .map(|due| due.date())
+        .map_err(|_| ParseError(format!("Invalid day '{}'. Use a date, today, tomorrow or a weekday like monday", input.trim())))
+}
+
+/// Whether a due date has already passed, read the same way `Todo::is_overdue` does.
+pub fn is_past(due: NaiveDateTime, tz: Option<&str>, now: DateTime<Local>) -> bool {
+    due_instant(due, tz) < now
+}
+
+/// A stored due date as a moment in time. Without a zone it is this
+/// machine's wall-clock time. With an IANA zone like `America/New_York` it
+/// is that zone's wall-clock time, wherever this machine is. A zone that no
+/// longer parses, e.g. after hand-editing, falls back to local.
+pub fn due_instant(due: NaiveDateTime, tz: Option<&str>) -> DateTime<Local> {
+    due_instant_in(due, tz, &Local)
+}
+
+/// `due_instant` with `machine` standing in for the local zone.
+pub fn due_instant_in<Z: TimeZone>(due: NaiveDateTime, tz: Option<&str>, machine: &Z) -> DateTime<Z> {
+    let zoned = tz.and_then(|name| name.parse::<Tz>().ok())
+        .and_then(|tz| wall_clock(&tz, due))
+        .map(|at| at.with_timezone(machine));
+    zoned.or_else(|| wall_clock(machine, due))
+        .unwrap_or_else(|| machine.from_utc_datetime(&due))
+}
+
+/// `at` on the clocks of `tz`. Ambiguous fall-back times take the earlier
+/// reading; in a spring-forward gap the wall time never happens, so an hour
+/// later is used.
+fn wall_clock<Z: TimeZone>(tz: &Z, at: NaiveDateTime) -> Option<DateTime<Z>> {
+    tz.from_local_datetime(&at)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(at + Duration::hours(1))).earliest())
+}
+
+/// Checks an IANA time zone name like `America/New_York`.
+pub fn parse_tz(input: &str) -> Result<String> {
+    input.trim().parse::<Tz>()
+        .map(|tz| tz.name().to_string())
+        .map_err(|_| ParseError(format!("Unknown time zone '{}'. Use an IANA name like America/New_York or Europe/Berlin", input.trim())))
+}
+
+/// `due` moved off a weekend: Saturdays and Sundays become the Monday after,
+/// at the same time of day. Weekdays are returned unchanged.
+pub fn skip_weekend(due: NaiveDateTime) -> NaiveDateTime {
+    match due.weekday() {
+        Weekday::Sat => due + Duration::days(2),
+        Weekday::Sun => due + Duration::days(1),
+        _ => due,
+    }
+}
+
+/// Parses a lead time like `30m`, `4h`, `2d` or `1w` into minutes. A bare
+/// number is minutes.
+pub fn parse_offset(input: &str) -> Result<i64> {
+    parse_minutes(input)
+        .ok_or_else(|| ParseError(format!("Invalid reminder offset '{}'. Use e.g. 30m, 4h, 2d or 1w", input.trim())))
+}
+
+/// Parses how long a todo should take, in the same units as `parse_offset`.
+pub fn parse_estimate(input: &str) -> Result<i64> {
+    parse_minutes(input)
+        .ok_or_else(|| ParseError(format!("Invalid estimate '{}'. Use e.g. 15m, 2h or 1d", input.trim())))
+}
+
+/// A positive amount with an optional m/h/d/w unit, in minutes.
+fn parse_minutes(input: &str) -> Option<i64> {
+    let trimmed = input.trim().to_lowercase();
+    let split = trimmed.find(|c: char| !c.is_ascii_digit()).unwrap_or(trimmed.len());
+    let (amount, unit) = trimmed.split_at(split);
+    let minutes_per = match unit.trim() {
+        "" | "m" | "min" | "mins" | "minutes" => 1,
+        "h" | "hr" | "hrs" | "hours" => 60,
+        "d" | "day" | "days" => 60 * 24,
+        "w" | "week" | "weeks" => 60 * 24 * 7,
+        _ => return None,
+    };
+    amount.parse::<i64>().ok()
+        .filter(|&n| n > 0)
+        .map(|n| n * minutes_per)
+}
+
+/// How `todo defer` moves a due date.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deferral {
+    /// Push it back by this much.
+    By(Duration),
+    /// Make it due at the end of this day.
+    Until(NaiveDate),
+}
+
+impl Deferral {
+    /// Accepts `tomorrow` or an amount like `3h`, `1d` or `1w`.
+    pub fn parse(input: &str, today: NaiveDate) -> Result<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "tomorrow" => Ok(Deferral::Until(today + Duration::days(1))),
+            _ => parse_offset(input)
+                .map(|minutes| Deferral::By(Duration::minutes(minutes)))
+                .map_err(|_| ParseError(format!("Invalid deferral '{}'. Use tomorrow or an amount like 3h, 1d or 1w", input.trim()))),
+        }
+    }
+
+    /// The new due date. Without a current one, amounts count from the end
+    /// of today, like a freshly typed due date.
+    pub fn apply(&self, due: Option<NaiveDateTime>, today: NaiveDate) -> NaiveDateTime {
+        match *self {
+            Deferral::Until(day) => day.and_time(end_of_day()),
+            Deferral::By(amount) => due.unwrap_or_else(|| today.and_time(end_of_day())) + amount,
+        }
+    }
+}
+
+/// Shortest exact rendering of a minute count, e.g. `90` → `90m`, `1440` → `1d`.
+pub fn format_offset(minutes: i64) -> String {
+    if minutes % (60 * 24 * 7) == 0 {
+        format!("{}w", minutes / (60 * 24 * 7))
+    } else if minutes % (60 * 24) == 0 {
+        format!("{}d", minutes / (60 * 24))
+    } else if minutes % 60 == 0 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Hours and minutes for time spent or planned, e.g. `45m`, `2h`, `1h 30m`.
+pub fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).expect("valid time")
+}
+
+/// Whether `format` is a `strftime` pattern chrono can render. Formatting with
+/// a malformed pattern panics, so user-supplied formats are checked first.
+pub fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn unzoned_due_dates_are_wall_clock_time_on_any_machine() {
+        let due = at("2026-10-14 14:22");
+        for machine in [chrono_tz::America::Los_Angeles, chrono_tz::Europe::Berlin, chrono_tz::Asia::Tokyo] {
+            let instant = due_instant_in(due, None, &machine);
+            assert_eq!(instant.naive_local(), due, "{machine}");
+
+            // Two hours before the deadline on that machine's clock it is not overdue yet
+            let now = machine.from_local_datetime(&at("2026-10-14 12:22")).unwrap();
+            assert!(instant > now, "{machine}");
+            let later = machine.from_local_datetime(&at("2026-10-14 14:23")).unwrap();
+            assert!(instant < later, "{machine}");
+        }
+    }
+
+    #[test]
+    fn zoned_due_dates_are_the_same_instant_on_every_machine() {
+        let due = at("2026-10-14 09:00");
+        let in_la = due_instant_in(due, Some("America/New_York"), &chrono_tz::America::Los_Angeles);
+        let in_berlin = due_instant_in(due, Some("America/New_York"), &chrono_tz::Europe::Berlin);
+        assert_eq!(in_la, in_berlin);
+        assert_eq!(in_la.naive_utc(), at("2026-10-14 13:00"));
+
+        // So overdue status agrees: one real moment, read on both machines
+        let now = Utc.from_utc_datetime(&at("2026-10-14 12:30"));
+        assert_eq!(in_la > now, in_berlin > now);
+        assert!(in_la > now);
+    }
+
+    #[test]
+    fn spring_forward_gap_moves_an_hour_later() {
+        // 02:30 doesn't exist in New York on 2026-03-08
+        let instant = due_instant_in(at("2026-03-08 02:30"), Some("America/New_York"), &Utc);
+        assert_eq!(instant.naive_utc(), at("2026-03-08 07:30"));
+    }
+
+    #[test]
+    fn unknown_zone_falls_back_to_the_machine() {
+        let due = at("2026-10-14 14:22");
+        let instant = due_instant_in(due, Some("Mars/Olympus"), &chrono_tz::Europe::Berlin);
+        assert_eq!(instant.naive_local(), due);
+    }
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn weeks_starting_monday_run_monday_to_sunday() {
+        // 2026-10-11 is a Sunday
+        assert_eq!(WeekStart::Monday.start_of_week(day("2026-10-11")), day("2026-10-05"));
+        assert_eq!(WeekStart::Monday.start_of_week(day("2026-10-12")), day("2026-10-12"));
+        assert_eq!(WeekStart::Monday.start_of_week(day("2026-10-14")), day("2026-10-12"));
+        assert_eq!(WeekStart::Monday.start_of_week(day("2026-10-18")), day("2026-10-12"));
+        assert_eq!(WeekStart::Monday.last_day(), Weekday::Sun);
+    }
+
+    #[test]
+    fn weeks_starting_sunday_run_sunday_to_saturday() {
+        assert_eq!(WeekStart::Sunday.start_of_week(day("2026-10-10")), day("2026-10-04"));
+        assert_eq!(WeekStart::Sunday.start_of_week(day("2026-10-11")), day("2026-10-11"));
+        assert_eq!(WeekStart::Sunday.start_of_week(day("2026-10-14")), day("2026-10-11"));
+        assert_eq!(WeekStart::Sunday.start_of_week(day("2026-10-17")), day("2026-10-11"));
+        assert_eq!(WeekStart::Sunday.last_day(), Weekday::Sat);
+    }
+
+    #[test]
+    fn this_week_follows_the_configured_start() {
+        let sunday = day("2026-10-11");
+        assert_eq!(Period::Week.first_day(sunday, WeekStart::Monday), day("2026-10-05"));
+        assert_eq!(Period::Week.first_day(sunday, WeekStart::Sunday), sunday);
+    }
+
+    #[test]
+    fn weekends_roll_forward_to_monday_at_the_same_time() {
+        // 2026-10-16 is a Friday
+        assert_eq!(skip_weekend(at("2026-10-16 17:00")), at("2026-10-16 17:00"));
+        assert_eq!(skip_weekend(at("2026-10-17 09:30")), at("2026-10-19 09:30"));
+        assert_eq!(skip_weekend(at("2026-10-18 23:59")), at("2026-10-19 23:59"));
+        assert_eq!(skip_weekend(at("2026-10-19 08:00")), at("2026-10-19 08:00"));
+    }
+}
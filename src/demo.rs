@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use chrono::{Duration, Local, NaiveDateTime, Utc};
+use std::collections::HashMap;
+
+use crate::auth::{FailedLogins, Session, User};
+use crate::config::UserSettings;
+use crate::storage::Storage;
+use crate::todo::{Priority, Recurrence, Status, Subtask, Todo};
+
+const USERNAME: &str = "demo";
+/// Lets `--demo login` work too. Only ever hashed into memory.
+const PASSWORD: &str = "demo";
+
+/// An in-memory store holding one signed-in demo user and a handful of
+/// sample todos, with due dates relative to now so reminders have something
+/// to show. Nothing is read from or written to disk.
+pub fn storage() -> Result<Storage> {
+    let storage = Storage::in_memory()?;
+    let now = Utc::now();
+
+    let user = User {
+        id: uuid::Uuid::new_v4().to_string(),
+        username: USERNAME.to_string(),
+        email: "demo@example.com".to_string(),
+        // The lowest cost bcrypt allows; this hash never leaves the process
+        password_hash: bcrypt::hash(PASSWORD, 4).context("Failed to set up the demo user")?,
+        created_at: now - Duration::days(30),
+        last_login: Some(now),
+        settings: UserSettings::default(),
+        is_admin: true,
+        failed_logins: FailedLogins::default(),
+    };
+    storage.save_users(&HashMap::from([(user.id.clone(), user.clone())]))?;
+    storage.save_session(&Session {
+        user_id: user.id.clone(),
+        created_at: now,
+        expires_at: now + Duration::days(1),
+        last_activity: now,
+        context: None,
+    })?;
+
+    let todos: HashMap<String, Todo> = sample_todos(&user.id).into_iter()
+        .map(|todo| (todo.id.clone(), todo))
+        .collect();
+    storage.save_todos(&todos)?;
+    storage.regenerate_markdown()?;
+    Ok(storage)
+}
+
+fn sample_todos(user_id: &str) -> Vec<Todo> {
+    let today = Local::now().date_naive();
+    let at = |days: i64, hour: u32| -> Option<NaiveDateTime> {
+        (today + Duration::days(days)).and_hms_opt(hour, 0, 0)
+    };
+    let todo = |title: &str, priority: Priority, due: Option<NaiveDateTime>| {
+        Todo::new(title.to_string(), None, priority, due, user_id.to_string())
+    };
+
+    let mut report = todo("Send quarterly report", Priority::High, at(-2, 17));
+    report.description = Some("Numbers are in the shared sheet; needs a summary up top.".to_string());
+    report.project = Some("work".to_string());
+    report.tags = vec!["finance".to_string()];
+
+    let mut review = todo("Review pull requests", Priority::High, at(0, 15));
+    review.project = Some("work".to_string());
+    review.estimate_minutes = Some(45);
+
+    let mut trip = todo("Plan weekend trip", Priority::Medium, at(5, 12));
+    trip.tags = vec!["personal".to_string()];
+    trip.subtasks = ["Book train", "Find a place to stay", "Pack"].iter()
+        .map(|title| Subtask { title: title.to_string(), done: *title == "Book train" })
+        .collect();
+
+    let mut plants = todo("Water the plants", Priority::Low, at(1, 9));
+    plants.recurrence = Some(Recurrence::Weekly);
+    plants.tags = vec!["home".to_string()];
+
+    let mut book = todo("Read a chapter of the book club pick", Priority::Low, None);
+    book.tags = vec!["personal".to_string()];
+    book.created_at = Utc::now() - Duration::days(10);
+    book.updated_at = book.created_at;
+
+    let mut ukulele = todo("Learn to play the ukulele", Priority::Low, None);
+    ukulele.someday = true;
+
+    let mut groceries = todo("Buy groceries", Priority::Medium, at(-1, 18));
+    groceries.status = Status::Completed;
+    groceries.completed_at = Some(Utc::now() - Duration::hours(20));
+    groceries.completion_note = Some("Forgot the coffee".to_string());
+
+    vec![report, review, trip, plants, book, ukulele, groceries]
+}
@@ -0,0 +1,127 @@
+use anyhow::Error;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+
+use crate::auth::{AuthError, AuthManager};
+use crate::config::Config;
+use crate::dates;
+use crate::reminder::pluralize;
+use crate::storage::Storage;
+
+/// What `doctor --json` prints.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Report {
+    /// Whether every check passed.
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
+/// One line of the `todo doctor` report.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, ok: true, detail: detail.into() }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, ok: false, detail: detail.into() }
+    }
+
+    fn from_error(name: &'static str, error: Error) -> Self {
+        Self::fail(name, format!("{:#}", error))
+    }
+}
+
+/// Runs every check, in order, without stopping at the first failure. Only
+/// needs `Storage`, so it still works when the data files don't load.
+pub fn run_checks(storage: &mut Storage) -> Vec<Check> {
+    let (config_check, config) = check_config(storage);
+    // Read whichever todos file the configured format would write
+    storage.set_compression(config.compress_storage);
+    storage.set_id_length(config.id_length());
+    storage.set_markdown_sort(config.markdown_sort);
+
+    vec![
+        config_check,
+        check_data_dir(storage),
+        match storage.load_users() {
+            Ok(users) => Check::pass("users file", pluralize(users.len() as i64, "account")),
+            Err(e) => Check::from_error("users file", e),
+        },
+        match storage.load_todos() {
+            Ok(todos) => Check::pass("todos file", pluralize(todos.len() as i64, "todo")),
+            Err(e) => Check::from_error("todos file", e),
+        },
+        match storage.verify_markdown() {
+            Ok(drift) if drift.is_clean() => Check::pass("markdown", "todos.md matches the stored data"),
+            Ok(drift) => Check::fail("markdown", format!(
+                "todos.md is out of sync ({} missing, {} unexpected); run `todo verify --repair`",
+                pluralize(drift.missing.len() as i64, "line"),
+                pluralize(drift.unexpected.len() as i64, "line")
+            )),
+            Err(e) => Check::from_error("markdown", e),
+        },
+        check_session(storage, &config),
+    ]
+}
+
+fn check_config(storage: &Storage) -> (Check, Config) {
+    let config = match storage.load_config() {
+        Ok(config) => config,
+        Err(e) => return (Check::from_error("config", e), Config::default()),
+    };
+
+    let check = if !dates::is_valid_format(&config.date_format) {
+        Check::fail("config", format!("date_format {:?} is not a valid pattern", config.date_format))
+    } else if let Some(path) = config.common_passwords_file.as_ref().filter(|p| !p.is_file()) {
+        Check::fail("config", format!("common_passwords_file {} does not exist", path.display()))
+    } else if !storage.config_file().exists() {
+        Check::pass("config", "no config.json; using defaults")
+    } else {
+        Check::pass("config", storage.config_file().display().to_string())
+    };
+    (check, config)
+}
+
+/// Writes and removes a probe file, since permissions alone don't say
+/// whether a write will succeed (read-only mounts, full disks).
+fn check_data_dir(storage: &Storage) -> Check {
+    let probe = storage.data_dir().join(".doctor-probe");
+    match fs::write(&probe, b"ok").and_then(|_| fs::remove_file(&probe)) {
+        Ok(()) => Check::pass("data directory", format!("{} is writable", storage.data_dir().display())),
+        Err(e) => Check::fail("data directory", format!("{} is not writable: {}", storage.data_dir().display(), e)),
+    }
+}
+
+/// Being logged out, or having an expired session, is normal. A session
+/// that fails its signature or points at a deleted account is not.
+fn check_session(storage: &Storage, config: &Config) -> Check {
+    match storage.session_is_signed() {
+        Ok(true) => {},
+        Ok(false) => return Check::fail("session", "session.json failed its integrity check; log in again"),
+        Err(e) => return Check::from_error("session", e),
+    }
+
+    let auth_manager = match AuthManager::new(storage, config) {
+        Ok(auth_manager) => auth_manager,
+        Err(e) => return Check::from_error("session", e.into()),
+    };
+    match auth_manager.get_current_user() {
+        Ok(user) => Check::pass("session", format!("logged in as {}", user.username)),
+        Err(AuthError::UserNotFound) => Check::fail("session", "session belongs to an account that no longer exists"),
+        Err(e) => Check::pass("session", e.to_string()),
+    }
+}
+
+/// Whether every check passed.
+pub fn all_ok(checks: &[Check]) -> bool {
+    checks.iter().all(|c| c.ok)
+}
+
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+/// Declares `Icons` with one field per symbol the UI prints, its emoji and its
+/// plain-text fallback. The field name doubles as the override key in the
+/// `emojis` config section.
+macro_rules! icons {
+    ($($name:ident: $emoji:expr, $ascii:expr;)*) => {
+        #[derive(Debug, Clone)]
+        pub struct Icons {
+            $(pub $name: String,)*
+        }
+
+        impl Icons {
+            /// Picks the emoji or ASCII set, then applies any per-symbol overrides.
+            pub fn new(ascii: bool, overrides: &HashMap<String, String>) -> Self {
+                let mut icons = if ascii {
+                    Self { $($name: $ascii.to_string(),)* }
+                } else {
+                    Self { $($name: $emoji.to_string(),)* }
+                };
+                $(
+                    if let Some(symbol) = overrides.get(stringify!($name)) {
+                        icons.$name = symbol.clone();
+                    }
+                )*
+                icons
+            }
+        }
+    };
+}
+
+icons! {
+    success: "✅", "[ok]";
+    error: "❌", "[error]";
+    info: "ℹ️", "[i]";
+    warning: "⚠️", "[!]";
+    wave: "👋", "";
+    celebrate: "🎉", "";
+    sparkle: "✨", "";
+    streak: "🔥", "";
+    app: "🚀", "";
+    login: "🔐", "";
+    list: "📋", "*";
+    bell: "🔔", "*";
+    user: "👤", "*";
+    stats: "📊", "*";
+    trend: "📈", "*";
+    calendar: "📅", "*";
+    focus: "🎯", "*";
+    status_pending: "⏳", "[ ]";
+    status_completed: "✅", "[x]";
+    priority_low: "🟢", "[L]";
+    priority_medium: "🟡", "[M]";
+    priority_high: "🔴", "[H]";
+    description: "📝", "-";
+    project: "📁", "-";
+    tags: "🏷️ ", "-";
+    creator: "✍️ ", "-";
+    shared: "👥", "-";
+    due: "📅", "-";
+    overdue: "⚠️ ", "-";
+    urgent: "⚡", "!";
+    waiting: "⏸", "[w]";
+    someday: "🌱", "[?]";
+    estimate: "⏱", "~";
+    recurring: "🔁", "*";
+    milestone: "🚩", "-";
+    created: "🕒", "-";
+    completion_note: "🗒", "-";
+    bar_full: "█", "#";
+    bar_empty: "░", "-";
+    rule: "─", "-";
+    reminder_overdue: "🚨", "[!!]";
+    reminder_due_soon: "⏰", "[!]";
+    reminder_due_tomorrow: "📅", "[i]";
+    reminder_due_this_week: "📋", "[i]";
+    reminder_custom: "🔔", "[*]";
+    reminder_stale: "💭", "[~]";
+    reminder_waiting: "⏸", "[w]";
+    reminder_milestone: "🚩", "[m]";
+    reminder_clock_skew: "🕰️", "[?]";
+}
+
+/// Joins an icon and text, skipping the separator when the icon is blank
+/// (as many are in ASCII mode).
+pub fn label(icon: &str, text: &str) -> String {
+    if icon.is_empty() {
+        text.to_string()
+    } else {
+        format!("{} {}", icon, text)
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::dates::{self, DateOrder};
+use crate::todo::{self, Priority, Todo};
+
+/// One todo as other tools export it. Only `title` is required.
+#[derive(Debug, Deserialize)]
+pub struct ImportRecord {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    /// RFC 3339 (`2024-05-01T14:00:00+02:00`) or any date `add` accepts.
+    #[serde(default, alias = "due_date")]
+    pub due: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+/// Reads a JSON array of records.
+pub fn read_records(path: &Path) -> Result<Vec<ImportRecord>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {} as a JSON array of todos", path.display()))
+}
+
+/// Parses an imported due date. Timestamps with an offset keep their
+/// instant: they are stored as this machine's wall-clock time, which is how
+/// unzoned due dates are read back (`dates::due_instant`). Plain dates go
+/// through `parse_due_date` like typed ones.
+pub fn parse_due(input: &str, order: DateOrder) -> dates::Result<NaiveDateTime> {
+    parse_due_in(input, order, &Local)
+}
+
+/// `parse_due` with `machine` standing in for the local zone.
+pub fn parse_due_in<Z: TimeZone>(input: &str, order: DateOrder, machine: &Z) -> dates::Result<NaiveDateTime> {
+    match DateTime::parse_from_rfc3339(input.trim()) {
+        Ok(due) => Ok(due.with_timezone(machine).naive_local()),
+        Err(_) => dates::parse_due_date(input, order),
+    }
+}
+
+impl ImportRecord {
+    pub fn into_todo(self, user_id: &str, order: DateOrder, default_priority: &Priority) -> Result<Todo> {
+        let priority = match self.priority {
+            Some(p) => Priority::from_string(&p)?,
+            None => default_priority.clone(),
+        };
+        let due_date = self.due
+            .filter(|d| !d.trim().is_empty())
+            .map(|d| parse_due(&d, order))
+            .transpose()?;
+        let description = self.description.filter(|d| !d.trim().is_empty());
+
+        let title = todo::validate_title(&self.title)?;
+        let mut todo = Todo::new(title, description, priority, due_date, user_id.to_string());
+        todo.project = self.project
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        for tag in self.tags.iter().map(|t| todo::normalize_tag(t)) {
+            if !tag.is_empty() && !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        }
+        Ok(todo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_timestamps_keep_their_instant_on_any_machine() {
+        let input = "2024-05-01T14:00:00+02:00";
+        let expected = DateTime::parse_from_rfc3339(input).unwrap();
+        for machine in [chrono_tz::America::Los_Angeles, chrono_tz::Europe::Berlin, chrono_tz::Asia::Tokyo] {
+            let stored = parse_due_in(input, DateOrder::Dmy, &machine).unwrap();
+            assert_eq!(dates::due_instant_in(stored, None, &machine), expected, "{machine}");
+        }
+    }
+}
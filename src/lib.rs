@@ -0,0 +1,18 @@
+//! The modules behind the `todo-cli` binary, as a library so benchmarks
+//! can call into them. `main.rs` holds the command line itself.
+
+pub mod auth;
+pub mod batch;
+pub mod config;
+pub mod dashboard;
+pub mod dates;
+pub mod demo;
+pub mod doctor;
+pub mod icons;
+pub mod import;
+pub mod migrations;
+pub mod todo;
+pub mod storage;
+pub mod reminder;
+pub mod stats;
+pub mod webhook;
@@ -1,56 +1,98 @@
-use anyhow::{Result};
-use chrono::{DateTime, Local};
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, NaiveDate};
+use clap::{Args, Parser, Subcommand};
 use colored::*;
-use dialoguer::{Input, Password, Select};
+use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
+use std::collections::{BTreeMap, HashSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
-mod auth;
-mod todo;
-mod storage;
-mod reminder;
+use todo_cli::{auth, batch, config, dashboard, dates, demo, doctor, icons, import, todo, storage, reminder, stats, webhook};
 
-use auth::AuthManager;
-use todo::{Todo, TodoManager, Priority, Status};
-use storage::Storage;
-use reminder::ReminderService;
+use auth::{AuthError, AuthManager};
+use config::{Config, ListFormat, UserSettings};
+use dates::{parse_due_date, DateOrder, Period};
+use icons::{Icons, label};
+use todo::{Blocker, QuickAdd, Recurrence, Search, SearchField, Todo, TodoError, TodoManager, TodoFilter, Priority, Status, SortKey, sort_todos};
+use storage::{HistoryEntry, Storage};
+use reminder::{Aging, ReminderService, pluralize};
+use stats::{AccountStatus, DataFile, Diagnostics, PriorityCounts, ReminderCounts, StatsReport, StatusReport};
+use webhook::Webhook;
 
 #[derive(Parser)]
 #[command(name = "todo")]
 #[command(about = "A CLI todo application with user authentication")]
 struct Cli {
+    /// Store all data and config in this directory instead of the platform default
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+    /// Try things out on sample todos kept in memory; nothing is saved
+    #[arg(long, global = true, conflicts_with = "data_dir")]
+    demo: bool,
+    /// Use plain-text markers instead of emoji
+    #[arg(long, global = true)]
+    ascii: bool,
+    /// Only print errors and the data you asked for
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Also print storage paths and timing to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    /// Don't send webhook events for this run
+    #[arg(long, global = true)]
+    no_webhook: bool,
+    /// Admins only: show another user's todos (list, status and overdue)
+    #[arg(long, global = true, value_name = "USERNAME")]
+    user: Option<String>,
+    /// Walk through the first-run guide again (when run without a command)
+    #[arg(long)]
+    welcome: bool,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+impl Cli {
+    /// The data store the flags point at: sample data in memory for
+    /// `--demo`, otherwise the data directory.
+    fn open_storage(&self) -> Result<Storage> {
+        if self.demo {
+            demo::storage()
+        } else {
+            Storage::new(self.data_dir.clone())
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Register a new user
-    Register,
+    Register(RegisterArgs),
     /// Login to your account
     Login,
     /// Logout from current session
     Logout,
-    /// Add a new todo item
-    Add {
-        #[arg(short, long)]
-        title: Option<String>,
-        #[arg(short, long)]
-        description: Option<String>,
-        #[arg(short, long)]
-        priority: Option<String>,
-        #[arg(short = 'd', long)]
-        due_date: Option<String>,
+    /// Look up the username registered with an email address
+    ForgotUsername {
+        email: String,
     },
+    /// Add a new todo item
+    Add(AddArgs),
     /// List all todos
-    List {
-        #[arg(short, long)]
-        status: Option<String>,
-        #[arg(short, long)]
-        priority: Option<String>,
-    },
+    List(ListArgs),
     /// Complete a todo
     Complete {
         id: Option<String>,
+        /// How it went, kept with the todo and in `history`
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Record something you already finished, straight into completed
+    Log {
+        title: String,
+    },
+    /// Reopen a completed todo
+    Reopen {
+        id: Option<String>,
     },
     /// Delete a todo
     Delete {
@@ -60,113 +102,1000 @@ enum Commands {
     Edit {
         id: Option<String>,
     },
+    /// Push a todo's due date back, e.g. `defer 2 --by 1d`
+    Defer {
+        /// Todo to defer; omit to pick one
+        id: Option<String>,
+        /// tomorrow, or an amount like 3h, 1d or 1w
+        #[arg(long)]
+        by: String,
+        /// Move the new due date off a weekend, to the Monday after
+        #[arg(long)]
+        business_days: bool,
+    },
+    /// Push back the due dates of every matching todo, e.g. after time away
+    Reschedule {
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Only todos that are overdue
+        #[arg(long)]
+        overdue: bool,
+        /// tomorrow, or an amount like 3h, 1d or 1w
+        #[arg(long)]
+        by: String,
+        /// Move new due dates off weekends, to the Monday after
+        #[arg(long)]
+        business_days: bool,
+    },
+    /// Mark a todo as waiting on someone else (hidden from --actionable)
+    Wait {
+        id: String,
+        /// Who or what it is waiting on
+        #[arg(long)]
+        on: Option<String>,
+        /// Stop waiting; the todo is actionable again
+        #[arg(long, conflicts_with = "on")]
+        clear: bool,
+    },
+    /// Change a todo's priority without going through `edit`
+    Priority {
+        /// low, medium or high
+        level: String,
+        /// Picked from your pending todos when left out
+        id: Option<String>,
+    },
+    /// Show today's focus list, or put a todo on it whatever its due date.
+    /// The list empties itself when the day changes
+    Focus {
+        id: Option<String>,
+        /// Take the todo off the list, or empty the list without an id
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Park a todo as someday/maybe, or commit to it again
+    Someday {
+        id: String,
+    },
+    /// Share a todo with another user
+    Share {
+        id: String,
+        username: String,
+    },
+    /// Add a tag to many todos at once
+    TagAdd(TagArgs),
+    /// Remove a tag from many todos at once
+    TagRemove(TagArgs),
+    /// Add a subtask to a todo
+    Subtask {
+        id: String,
+        title: String,
+    },
+    /// Toggle a subtask between done and not done
+    ToggleSubtask {
+        id: String,
+        /// Subtask number as shown in the list (starting at 1)
+        number: usize,
+    },
+    /// Add a milestone, an intermediate deadline before the due date
+    Milestone {
+        id: String,
+        label: String,
+        /// When the milestone is due
+        #[arg(short = 'd', long)]
+        date: String,
+    },
+    /// Mark a milestone done, or not done again
+    ToggleMilestone {
+        id: String,
+        /// Milestone number as shown in the list (starting at 1)
+        number: usize,
+    },
+    /// Month grid of due dates; --day lists one day's todos
+    Calendar {
+        /// YYYY-MM, or just the month number for this year (default: this month)
+        month: Option<String>,
+        /// Also list the todos due on this day of the month
+        #[arg(long)]
+        day: Option<u32>,
+    },
+    /// Full-screen list: c complete, d delete, / search, a actionable only, q quit
+    Dashboard,
+    /// Find todos by text in their title, description, tags or subtasks
+    Search {
+        query: String,
+        /// Only look in title, description, tags or subtasks
+        #[arg(long)]
+        field: Option<String>,
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+        #[command(flatten)]
+        view: ViewArgs,
+    },
     /// Show overdue todos
-    Overdue,
+    Overdue(ViewArgs),
     /// Show today's todos
-    Today,
+    Today(ViewArgs),
+    /// Show todos completed today, this week or this month
+    Completed {
+        /// today, week or month
+        #[arg(default_value = "today")]
+        period: String,
+    },
+    /// Suggest an order for today's and overdue todos, with running time
+    Plan,
     /// Check for reminders
-    Reminders,
+    Reminders {
+        /// Only the number of critical, warning and info reminders
+        #[arg(long)]
+        summary: bool,
+        /// Print the summary as JSON
+        #[arg(long, requires = "summary")]
+        json: bool,
+    },
+    /// Dismiss reminders until the todo changes or gets more urgent
+    Ack {
+        /// Todo to acknowledge; omit to acknowledge every current reminder
+        id: Option<String>,
+    },
+    /// Mute reminders for all of your pending todos for a while
+    SnoozeAll {
+        /// How many hours to stay quiet
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..), required_unless_present_any = ["until", "legacy_hours"])]
+        hours: Option<u32>,
+        /// Stay quiet until the start of a day: a date, tomorrow or a weekday like monday
+        #[arg(long, conflicts_with_all = ["hours", "legacy_hours"])]
+        until: Option<String>,
+        /// The hour count without --hours, as older versions took it
+        #[arg(value_name = "HOURS", hide = true, value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "hours")]
+        legacy_hours: Option<u32>,
+    },
+    /// Bring back reminders muted with snooze-all
+    UnsnoozeAll,
     /// Show user status
-    Status,
+    Status {
+        /// Print machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+        /// Also show the app version, where the data lives and how big it is
+        #[arg(long)]
+        diagnostics: bool,
+    },
+    /// View or change your personal settings
+    Settings(SettingsArgs),
+    /// Rebuild todos.md from the stored data
+    Regenerate {
+        /// Order within each section: priority, due, created or updated
+        /// (defaults to markdown_sort)
+        #[arg(long)]
+        sort: Option<String>,
+    },
+    /// Check that todos.md matches the stored data
+    Verify {
+        /// Rewrite todos.md without asking if it has drifted
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Check the data files, markdown export, session and config for problems
+    Doctor {
+        /// Print machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a compact pending!overdue summary for shell prompts
+    Prompt {
+        /// Template with {pending}, {overdue} and {today} placeholders
+        #[arg(long, default_value = "{pending}!{overdue}")]
+        format: String,
+    },
+    /// Work within a project: add and list default to it
+    Use {
+        project: Option<String>,
+        /// Go back to seeing every project
+        #[arg(long, conflicts_with = "project")]
+        clear: bool,
+    },
+    /// Rank users by todos completed this week (enable with "leaderboard" in config)
+    Leaderboard,
+    /// Show what you've done recently: completions with their notes, and more
+    History {
+        /// How many entries to show, newest first
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Admins only: clean up todos whose owner account no longer exists
+    Prune {
+        /// Archive orphaned todos instead of deleting them
+        #[arg(long)]
+        archive: bool,
+        /// Only report what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Add todos from a JSON file exported by another tool
+    Import {
+        /// JSON array of objects with title and optional description,
+        /// priority, due, tags and project
+        file: PathBuf,
+        /// Import without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Run JSON commands read from stdin, one per line, and print a JSON
+    /// result for each
+    Batch,
+    /// Show productivity statistics
+    Stats {
+        /// Print machine-readable JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the JSON Schema of a command's --json output
+    Schema {
+        #[arg(value_parser = ["list", "status", "stats", "doctor", "reminders"])]
+        command: String,
+    },
+}
+
+#[derive(Args, Clone, Default)]
+struct AddArgs {
+    #[arg(short, long)]
+    title: Option<String>,
+    #[arg(long)]
+    description: Option<String>,
+    #[arg(short, long)]
+    priority: Option<String>,
+    #[arg(short = 'd', long)]
+    due_date: Option<String>,
+    /// Tag the todo (repeatable)
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// Stable key identifying this todo, for scripts that sync from elsewhere
+    #[arg(long)]
+    key: Option<String>,
+    /// Update the todo with this --key if it exists, otherwise create it
+    #[arg(long, requires = "key")]
+    upsert: bool,
+    /// Accept a due date in the past without warning
+    #[arg(long)]
+    force: bool,
+    /// Project to file the todo under (defaults to the one picked with `use`)
+    #[arg(long)]
+    project: Option<String>,
+    /// Remind this long before the due date, e.g. 4h or 2d (repeatable;
+    /// replaces the global reminder thresholds for this todo)
+    #[arg(long = "remind-before", value_name = "DURATION")]
+    remind_before: Vec<String>,
+    /// How long it should take, e.g. 30m or 2h (used by `plan`)
+    #[arg(long, value_name = "DURATION")]
+    estimate: Option<String>,
+    /// Repeat daily, weekly or monthly: completing it adds the next one
+    #[arg(long, value_name = "PERIOD")]
+    every: Option<String>,
+    /// Take !priority, #tag, @project and due:<day> out of the title
+    #[arg(long)]
+    quick: bool,
+    /// Keep the title exactly as typed, even with quick_add in the config
+    #[arg(long, conflicts_with = "quick")]
+    literal: bool,
+    /// Warn about pending todos with nearly the same title before adding
+    #[arg(long, conflicts_with = "upsert")]
+    check_duplicates: bool,
+    /// Move a due date on a Saturday or Sunday to the Monday after
+    #[arg(long)]
+    business_days: bool,
+    /// Time zone the due date is in, e.g. America/New_York, for deadlines
+    /// that don't move when you travel
+    #[arg(long, value_name = "ZONE")]
+    tz: Option<String>,
+}
+
+/// Registration answers given up front. When all three are present no
+/// prompts are shown, so accounts can be provisioned from scripts.
+#[derive(Args, Clone, Default)]
+struct RegisterArgs {
+    #[arg(long, env = "TODO_REG_USERNAME")]
+    username: Option<String>,
+    #[arg(long, env = "TODO_REG_EMAIL")]
+    email: Option<String>,
+    /// Prefer the environment variable; flags show up in process listings
+    #[arg(long, env = "TODO_REG_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+}
+
+#[derive(Args, Clone, Default)]
+struct FilterArgs {
+    #[arg(short, long)]
+    status: Option<String>,
+    #[arg(short, long)]
+    priority: Option<String>,
+    /// Only todos carrying this tag
+    #[arg(long)]
+    tag: Option<String>,
+    /// Only todos in this project
+    #[arg(long)]
+    project: Option<String>,
+    /// Show archived todos instead of the live ones
+    #[arg(long)]
+    archived: bool,
+    /// Show someday/maybe todos instead of committed ones
+    #[arg(long)]
+    someday: bool,
+}
+
+impl FilterArgs {
+    fn to_filter(&self) -> Result<TodoFilter> {
+        Ok(TodoFilter {
+            status: self.status.as_deref().map(Status::from_string).transpose()?,
+            priority: self.priority.as_deref().map(Priority::from_string).transpose()?,
+            tag: self.tag.clone(),
+            project: self.project.clone(),
+            archived: self.archived,
+            someday: self.someday,
+        })
+    }
+}
+
+#[derive(Args, Clone, Default)]
+struct ListArgs {
+    #[command(flatten)]
+    filter: FilterArgs,
+    /// Ignore the project picked with `use`
+    #[arg(long, conflicts_with = "project")]
+    all_projects: bool,
+    /// Only todos you can work on now (pending, not waiting, someday or snoozed)
+    #[arg(long, conflicts_with = "status")]
+    actionable: bool,
+    /// Print the matching todos as a JSON array instead of text
+    #[arg(long, conflicts_with = "count")]
+    json: bool,
+    /// One line per todo: id, priority, status, title and due date
+    #[arg(long, conflicts_with_all = ["json", "detailed"])]
+    oneline: bool,
+    /// Every field of each todo (the default unless list_format says otherwise)
+    #[arg(long, conflicts_with = "json")]
+    detailed: bool,
+    /// Group todos under each of their tags, with counts per tag
+    #[arg(long, conflicts_with = "json")]
+    by_tag: bool,
+    #[command(flatten)]
+    view: ViewArgs,
+}
+
+/// Output options shared by every command that lists todos.
+#[derive(Args, Clone, Default)]
+struct ViewArgs {
+    /// Sort by priority, due, created or updated
+    #[arg(long)]
+    sort: Option<String>,
+    /// Print only the number of matching todos
+    #[arg(long)]
+    count: bool,
+}
+
+#[derive(Args, Clone)]
+struct TagArgs {
+    #[arg(value_name = "TAG")]
+    name: String,
+    /// Todo ids to change; omit to select by filter instead
+    ids: Vec<String>,
+    #[command(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(Args, Clone)]
+struct SettingsArgs {
+    /// Priority preselected when adding todos
+    #[arg(long)]
+    default_priority: Option<String>,
+    /// Read year-last dates as dmy or mdy
+    #[arg(long)]
+    date_order: Option<String>,
+    /// strftime pattern for displayed timestamps, e.g. "%d.%m.%Y %H:%M"
+    #[arg(long)]
+    date_format: Option<String>,
+    /// Remind about todos due within this many days
+    #[arg(long)]
+    upcoming_days: Option<i64>,
+    /// Nudge about undated todos pending longer than this many days
+    #[arg(long)]
+    stale_days: Option<i64>,
+    /// Forget all personal settings and use the global config again
+    #[arg(long, conflicts_with_all = ["default_priority", "date_order", "date_format", "upcoming_days", "stale_days"])]
+    reset: bool,
+}
+
+/// `validate_title` in the shape dialoguer wants, so prompts re-ask
+/// instead of failing.
+fn title_prompt_check(input: &str) -> std::result::Result<(), String> {
+    todo::validate_title(input).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// `YYYY-MM`, or a bare month number in `year`, as that month's first day.
+fn parse_month(input: &str, year: i32) -> Result<NaiveDate> {
+    let input = input.trim();
+    let parsed = match input.split_once('-') {
+        Some((y, m)) => y.parse().ok().zip(m.parse().ok()),
+        None => input.parse().ok().map(|m| (year, m)),
+    };
+    parsed
+        .and_then(|(y, m)| NaiveDate::from_ymd_opt(y, m, 1))
+        .ok_or_else(|| UsageError(format!("Invalid month '{}'. Use YYYY-MM or a month number", input)).into())
+}
+
+/// A formatted due date, followed by the zone for zoned deadlines so it
+/// doesn't read as local time.
+fn with_zone(shown: String, tz: Option<&str>) -> String {
+    match tz {
+        Some(tz) => format!("{} {}", shown, tz),
+        None => shown,
+    }
+}
+
+/// "waiting on Alice", or just "waiting" when nobody was named.
+fn waiting_label(on: Option<&str>) -> String {
+    match on {
+        Some(on) => format!("waiting on {}", on),
+        None => "waiting".to_string(),
+    }
+}
+
+/// A byte count for people: "512 B", "3.4 KB", "1.2 MB".
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1048575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+/// Exit statuses scripts can branch on; listed under "Exit Codes" in the README.
+const EXIT_FAILURE: i32 = 1;
+const EXIT_NOT_AUTHENTICATED: i32 = 2;
+const EXIT_NOT_FOUND: i32 = 3;
+const EXIT_INVALID: i32 = 4;
+const EXIT_STORAGE: i32 = 5;
+/// What a shell reports for a process killed by Ctrl-C.
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// A command line that parsed but asks for something that can't be done,
+/// like `--user` on a command that doesn't support it or a bad month.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct UsageError(String);
+
+/// Maps an error that reached `main` to its exit status, looking past any
+/// context added on the way. Anything without a more specific meaning exits
+/// with 1.
+fn exit_code(error: &anyhow::Error) -> i32 {
+    if error.chain().any(|cause| cause.is::<clap::Error>() || cause.is::<UsageError>() || cause.is::<dates::ParseError>()) {
+        return EXIT_INVALID;
+    }
+    if let Some(error) = error.chain().find_map(|cause| cause.downcast_ref::<AuthError>()) {
+        return match error {
+            AuthError::NoAccounts
+            | AuthError::NotAuthenticated
+            | AuthError::SessionExpired
+            | AuthError::SessionIdle
+            | AuthError::InvalidCredentials => EXIT_NOT_AUTHENTICATED,
+            // Logged in, just not allowed: logging in again won't help
+            AuthError::AdminOnly(_) => EXIT_FAILURE,
+            AuthError::UserNotFound => EXIT_NOT_FOUND,
+            AuthError::UsernameTaken
+            | AuthError::EmailTaken
+            | AuthError::EmptyUsername
+            | AuthError::InvalidEmail
+            | AuthError::PasswordTooShort(_)
+            | AuthError::CommonPassword => EXIT_INVALID,
+            AuthError::Storage(_) => EXIT_STORAGE,
+            AuthError::Hash(_) | AuthError::Verify(_) => EXIT_FAILURE,
+        };
+    }
+    if let Some(error) = error.chain().find_map(|cause| cause.downcast_ref::<TodoError>()) {
+        return match error {
+            TodoError::NotFound
+            | TodoError::SubtaskNotFound(_)
+            | TodoError::MilestoneNotFound(_)
+            | TodoError::NoSuchPosition(_) => EXIT_NOT_FOUND,
+            TodoError::NotCompleted
+            | TodoError::AlreadyCompleted
+            | TodoError::DeferBackwards
+            | TodoError::SnoozeInPast
+            | TodoError::OwnerOnly(_)
+            | TodoError::AlreadyOwner
+            | TodoError::AmbiguousReference(_)
+            | TodoError::MissingKey
+            | TodoError::DuplicateKey(_)
+            | TodoError::InvalidStatus(_)
+            | TodoError::InvalidPriority(_)
+            | TodoError::InvalidSort(_)
+            | TodoError::InvalidRecurrence(_)
+            | TodoError::InvalidSearchField(_)
+            | TodoError::InvalidPattern(_)
+            | TodoError::EmptyTitle
+            | TodoError::EmptyField(_)
+            | TodoError::ZeroNumber(_)
+            | TodoError::NothingSelected(_)
+            | TodoError::TooManyTodos(_) => EXIT_INVALID,
+            TodoError::Storage(_) => EXIT_STORAGE,
+        };
+    }
+    // Untyped errors from reading or parsing the data files; user-supplied
+    // files and stdin come back as `UsageError` above
+    if error.chain().any(|cause| cause.is::<std::io::Error>() || cause.is::<serde_json::Error>()) {
+        return EXIT_STORAGE;
+    }
+    EXIT_FAILURE
+}
+
+/// Ctrl-C at a prompt while `interactive_mode` is handling SIGINT, which
+/// should end the program as quietly as the signal would have.
+fn is_interrupt(error: &anyhow::Error) -> bool {
+    error.chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|e| e.kind() == std::io::ErrorKind::Interrupted)
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+async fn main() {
+    if let Err(error) = run().await {
+        if is_interrupt(&error) {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        match error.downcast_ref::<clap::Error>() {
+            // clap formats its own usage errors, hints included
+            Some(usage) => { let _ = usage.print(); },
+            // Same rendering anyhow uses when `main` returns an error
+            None => eprintln!("Error: {:?}", error),
+        }
+        std::process::exit(exit_code(&error));
+    }
+}
+
+async fn run() -> Result<()> {
+    // clap exits with 2 on bad usage, which here means "not authenticated"
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) if e.use_stderr() => return Err(e.into()),
+        Err(e) => e.exit(),
+    };
+    
+    // Runs on every shell prompt, so skip the full app setup and stay silent
+    // when logged out or when anything goes wrong
+    if let Some(Commands::Prompt { format }) = &cli.command {
+        if let Ok(Some(line)) = cli.open_storage().and_then(|storage| prompt_line(&storage, format)) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+    
+    // Schemas describe types, not data, so there's nothing to load
+    if let Some(Commands::Schema { command }) = &cli.command {
+        println!("{}", serde_json::to_string_pretty(&output_schema(command))?);
+        return Ok(());
+    }
     
-    let mut app = TodoApp::new()?;
+    // Has to work even when the data files are too broken for the app to load
+    if let Some(Commands::Doctor { json }) = &cli.command {
+        if cli.demo {
+            return Err(UsageError("doctor checks the files on disk, so it can't run with --demo".to_string()).into());
+        }
+        return run_doctor(cli.data_dir.clone(), cli.ascii, *json);
+    }
+    
+    let started = std::time::Instant::now();
+    let verbosity = if cli.quiet {
+        Verbosity::Quiet
+    } else if cli.verbose {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    };
+    
+    let mut app = TodoApp::new(cli.open_storage()?, cli.ascii, verbosity, !cli.no_webhook)?;
+    app.print_diagnostics();
+    
+    if let Some(username) = &cli.user {
+        let view = match &cli.command {
+            Some(Commands::List(_)) => "list",
+            Some(Commands::Status { .. }) => "status",
+            Some(Commands::Overdue(_)) => "overdue",
+            _ => return Err(UsageError("--user only works with list, status and overdue".to_string()).into()),
+        };
+        app.ensure_authenticated().await?;
+        app.view_as(username, view)?;
+    }
     
     match &cli.command {
-        Some(Commands::Register) => app.register().await?,
+        Some(Commands::Register(args)) => app.register(args.clone()).await?,
         Some(Commands::Login) => app.login().await?,
         Some(Commands::Logout) => app.logout().await?,
-        Some(Commands::Add { title, description, priority, due_date }) => {
-            app.ensure_authenticated()?;
-            app.add_todo(title.clone(), description.clone(), priority.clone(), due_date.clone()).await?;
+        Some(Commands::ForgotUsername { email }) => app.forgot_username(email),
+        Some(Commands::Add(args)) => {
+            app.ensure_authenticated().await?;
+            app.add_todo(args.clone()).await?;
+        },
+        Some(Commands::List(args)) => {
+            app.ensure_authenticated().await?;
+            app.list_todos(args.clone()).await?;
         },
-        Some(Commands::List { status, priority }) => {
-            app.ensure_authenticated()?;
-            app.list_todos(status.clone(), priority.clone()).await?;
+        Some(Commands::TagAdd(args)) => {
+            app.ensure_authenticated().await?;
+            app.modify_tags(args, true).await?;
         },
-        Some(Commands::Complete { id }) => {
-            app.ensure_authenticated()?;
-            app.complete_todo(id.clone()).await?;
+        Some(Commands::TagRemove(args)) => {
+            app.ensure_authenticated().await?;
+            app.modify_tags(args, false).await?;
+        },
+        Some(Commands::Complete { id, note }) => {
+            app.ensure_authenticated().await?;
+            app.complete_todo(id.clone(), note.clone()).await?;
+        },
+        Some(Commands::Log { title }) => {
+            app.ensure_authenticated().await?;
+            app.log_done(title.clone()).await?;
+        },
+        Some(Commands::Reopen { id }) => {
+            app.ensure_authenticated().await?;
+            app.reopen_todo(id.clone()).await?;
         },
         Some(Commands::Delete { id }) => {
-            app.ensure_authenticated()?;
+            app.ensure_authenticated().await?;
             app.delete_todo(id.clone()).await?;
         },
         Some(Commands::Edit { id }) => {
-            app.ensure_authenticated()?;
+            app.ensure_authenticated().await?;
             app.edit_todo(id.clone()).await?;
         },
-        Some(Commands::Overdue) => {
-            app.ensure_authenticated()?;
-            app.show_overdue().await?;
+        Some(Commands::Defer { id, by, business_days }) => {
+            app.ensure_authenticated().await?;
+            app.defer_todo(id.clone(), by, *business_days).await?;
+        },
+        Some(Commands::Reschedule { filter, overdue, by, business_days }) => {
+            app.ensure_authenticated().await?;
+            app.reschedule(filter, *overdue, by, *business_days).await?;
+        },
+        Some(Commands::Wait { id, on, clear }) => {
+            app.ensure_authenticated().await?;
+            app.set_waiting(id, on.clone(), *clear).await?;
+        },
+        Some(Commands::Priority { level, id }) => {
+            app.ensure_authenticated().await?;
+            app.set_priority(level, id.clone()).await?;
+        },
+        Some(Commands::Focus { id, clear }) => {
+            app.ensure_authenticated().await?;
+            app.focus(id.as_deref(), *clear).await?;
+        },
+        Some(Commands::Someday { id }) => {
+            app.ensure_authenticated().await?;
+            app.toggle_someday(id).await?;
+        },
+        Some(Commands::Share { id, username }) => {
+            app.ensure_authenticated().await?;
+            app.share_todo(id, username).await?;
+        },
+        Some(Commands::Subtask { id, title }) => {
+            app.ensure_authenticated().await?;
+            app.add_subtask(id, title.clone()).await?;
+        },
+        Some(Commands::ToggleSubtask { id, number }) => {
+            app.ensure_authenticated().await?;
+            app.toggle_subtask(id, *number).await?;
+        },
+        Some(Commands::Milestone { id, label, date }) => {
+            app.ensure_authenticated().await?;
+            app.add_milestone(id, label, date).await?;
+        },
+        Some(Commands::ToggleMilestone { id, number }) => {
+            app.ensure_authenticated().await?;
+            app.toggle_milestone(id, *number).await?;
+        },
+        Some(Commands::Overdue(args)) => {
+            app.ensure_authenticated().await?;
+            app.show_overdue(args.clone()).await?;
+        },
+        Some(Commands::Plan) => {
+            app.ensure_authenticated().await?;
+            app.show_plan().await?;
+        },
+        Some(Commands::Search { query, field, regex, view }) => {
+            app.ensure_authenticated().await?;
+            app.search_todos(query, field.as_deref(), *regex, view.clone()).await?;
         },
-        Some(Commands::Today) => {
-            app.ensure_authenticated()?;
-            app.show_today().await?;
+        Some(Commands::Today(args)) => {
+            app.ensure_authenticated().await?;
+            app.show_today(args.clone()).await?;
         },
-        Some(Commands::Reminders) => {
-            app.ensure_authenticated()?;
-            app.check_reminders().await?;
+        Some(Commands::Completed { period }) => {
+            app.ensure_authenticated().await?;
+            app.show_completed(period).await?;
         },
-        Some(Commands::Status) => {
-            app.show_status().await?;
+        Some(Commands::Calendar { month, day }) => {
+            app.ensure_authenticated().await?;
+            app.show_calendar(month.as_deref(), *day).await?;
+        },
+        Some(Commands::Dashboard) => {
+            app.ensure_authenticated().await?;
+            app.dashboard().await?;
+        },
+        Some(Commands::Reminders { summary: true, json }) => {
+            app.ensure_authenticated().await?;
+            app.reminder_summary(*json).await?;
+        },
+        Some(Commands::Reminders { summary: false, .. }) => {
+            app.ensure_authenticated().await?;
+            app.check_reminders(None).await?;
+        },
+        Some(Commands::Ack { id }) => {
+            app.ensure_authenticated().await?;
+            app.acknowledge(id.clone()).await?;
+        },
+        Some(Commands::SnoozeAll { hours, until, legacy_hours }) => {
+            app.ensure_authenticated().await?;
+            let snooze = match (until, hours.or(*legacy_hours)) {
+                (Some(day), _) => Snooze::Until(day.clone()),
+                (None, Some(hours)) => Snooze::Hours(hours),
+                (None, None) => unreachable!("clap requires --hours or --until"),
+            };
+            app.snooze_all(Some(snooze)).await?;
+        },
+        Some(Commands::UnsnoozeAll) => {
+            app.ensure_authenticated().await?;
+            app.snooze_all(None).await?;
+        },
+        Some(Commands::Status { json, diagnostics }) => {
+            app.show_status(*json, *diagnostics).await?;
+        },
+        Some(Commands::Settings(args)) => {
+            app.ensure_authenticated().await?;
+            app.settings(args).await?;
+        },
+        Some(Commands::Regenerate { sort }) => {
+            app.regenerate(sort.clone())?;
+        },
+        Some(Commands::Verify { repair }) => {
+            app.verify(*repair)?;
+        },
+        Some(Commands::Prompt { .. } | Commands::Doctor { .. } | Commands::Schema { .. }) => {}, // handled before the app is built
+        Some(Commands::Use { project, clear }) => {
+            app.ensure_authenticated().await?;
+            app.use_project(project.clone(), *clear)?;
+        },
+        Some(Commands::Leaderboard) => {
+            app.ensure_authenticated().await?;
+            app.show_leaderboard()?;
+        },
+        Some(Commands::History { limit }) => {
+            app.ensure_authenticated().await?;
+            app.show_history(*limit)?;
+        },
+        Some(Commands::Prune { archive, dry_run }) => {
+            app.ensure_authenticated().await?;
+            app.prune(*archive, *dry_run).await?;
+        },
+        Some(Commands::Import { file, yes }) => {
+            app.ensure_authenticated().await?;
+            app.import_todos(file, *yes).await?;
+        },
+        Some(Commands::Batch) => {
+            app.ensure_authenticated().await?;
+            app.run_batch().await?;
+        },
+        Some(Commands::Stats { json }) => {
+            app.ensure_authenticated().await?;
+            app.show_stats(*json).await?;
         },
         None => {
-            app.interactive_mode().await?;
+            app.interactive_mode(cli.welcome).await?;
         }
     }
+    app.todo_manager.flush().await?;
+    
+    if verbosity == Verbosity::Verbose {
+        eprintln!("[verbose] finished in {:.1?}", started.elapsed());
+    }
     
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+/// How long `snooze-all` should last.
+enum Snooze {
+    Hours(u32),
+    /// A day as typed, read with `dates::parse_day`.
+    Until(String),
+}
+
 struct TodoApp {
     auth_manager: AuthManager,
     todo_manager: TodoManager,
     storage: Storage,
     reminder_service: ReminderService,
+    /// Settings from `config.json`, before any per-user overrides.
+    global_config: Config,
+    /// `global_config` with the signed-in user's settings applied.
+    config: Config,
+    icons: Icons,
+    verbosity: Verbosity,
+    /// Whose todos read-only views show when an admin passes `--user`.
+    viewing: Option<auth::User>,
 }
 
 impl TodoApp {
-    fn new() -> Result<Self> {
-        let storage = Storage::new()?;
-        let auth_manager = AuthManager::new(&storage)?;
-        let todo_manager = TodoManager::new(&storage)?;
-        let reminder_service = ReminderService::new();
+    fn new(mut storage: Storage, ascii: bool, verbosity: Verbosity, webhooks: bool) -> Result<Self> {
+        let config = storage.load_config()?;
+        storage.set_compression(config.compress_storage);
+        storage.set_id_length(config.id_length());
+        storage.set_markdown_sort(config.markdown_sort);
+        let auth_manager = AuthManager::new(&storage, &config)?;
+        let mut todo_manager = TodoManager::new(&storage)?;
+        todo_manager.set_archive_recurrences(config.archive_completed_recurrences);
+        todo_manager.set_max_open(config.max_todos_per_user);
+        todo_manager.set_skip_weekends(config.skip_weekends);
+        todo_manager.set_auto_save(config.auto_save);
+        todo_manager.roll_over_focus(Local::now().date_naive())?;
+        if webhooks {
+            todo_manager.set_webhook(Webhook::from_config(&config.webhooks));
+        }
+        let icons = Icons::new(ascii || config.ascii, &config.emojis);
+        let reminder_service = ReminderService::new(&config, icons.clone());
         
-        Ok(Self {
+        let mut app = Self {
             auth_manager,
             todo_manager,
             storage,
             reminder_service,
-        })
+            global_config: config.clone(),
+            config,
+            icons,
+            verbosity,
+            viewing: None,
+        };
+        app.apply_user_settings();
+        Ok(app)
+    }
+    
+    /// Prints a confirmation or decoration line; dropped with `--quiet`.
+    fn note(&self, message: impl std::fmt::Display) {
+        if self.verbosity > Verbosity::Quiet {
+            println!("{}", message);
+        }
+    }
+    
+    /// With `--verbose`, reports where data and config are read from.
+    fn print_diagnostics(&self) {
+        if self.verbosity < Verbosity::Verbose {
+            return;
+        }
+        eprintln!("[verbose] data dir: {}", self.storage.data_dir().display());
+        eprintln!("[verbose] config file: {}", self.storage.config_file().display());
+        match self.auth_manager.get_current_user() {
+            Ok(user) => eprintln!("[verbose] session: {} ({})", user.username, user.id),
+            Err(e) => eprintln!("[verbose] session: none ({})", e),
+        }
+    }
+    
+    /// Recomputes the effective config for whoever is signed in now.
+    fn apply_user_settings(&mut self) {
+        self.config = match self.auth_manager.get_current_user() {
+            Ok(user) => self.global_config.with_user_settings(&user.settings),
+            Err(_) => self.global_config.clone(),
+        };
+        self.reminder_service = ReminderService::new(&self.config, self.icons.clone());
+    }
+    
+    /// A todo id shortened for display: `id_display_length` characters, or
+    /// more if that many would be ambiguous.
+    fn short_id<'a>(&self, id: &'a str) -> &'a str {
+        todo::abbreviate(id, self.todo_manager.id_display_length(self.config.id_length()))
+    }
+    
+    /// Resolves a per-command `--sort` override, falling back to the configured default.
+    fn sort_key(&self, sort: Option<String>) -> Result<SortKey> {
+        match sort {
+            Some(s) => Ok(SortKey::from_string(&s)?),
+            None => Ok(self.config.default_sort),
+        }
     }
     
-    async fn register(&mut self) -> Result<()> {
-        println!("{}", "🚀 Welcome to Todo CLI - Registration".bright_cyan().bold());
+    /// Points read-only views at `username`'s todos. Admins only; every use
+    /// is recorded in the history log.
+    fn view_as(&mut self, username: &str, view: &str) -> Result<()> {
+        let admin = self.auth_manager.get_current_user()?;
+        if !admin.is_admin {
+            return Err(AuthError::AdminOnly("view other users' todos").into());
+        }
+        let target = self.auth_manager.find_by_username(username)
+            .cloned()
+            .ok_or(AuthError::UserNotFound)?;
         
-        let username: String = Input::new()
-            .with_prompt("Username")
-            .interact_text()?;
+        self.storage.append_history(&HistoryEntry {
+            at: chrono::Utc::now(),
+            user_id: admin.id.clone(),
+            action: "admin_view".to_string(),
+            detail: format!("{} as {}", view, target.username),
+        })?;
+        if self.verbosity == Verbosity::Verbose {
+            eprintln!("[verbose] viewing {} as {}", view, target.username);
+        }
+        self.viewing = Some(target);
+        Ok(())
+    }
+    
+    /// The user read-only views are about: the `--user` target, or whoever
+    /// is signed in.
+    fn subject(&self) -> Result<auth::User> {
+        match &self.viewing {
+            Some(user) => Ok(user.clone()),
+            None => Ok(self.auth_manager.get_current_user()?),
+        }
+    }
+    
+    /// Resolves an id, id prefix, `last` or list position for the current user.
+    fn resolve_id(&self, reference: &str) -> Result<String> {
+        let current_user = self.auth_manager.get_current_user()?;
+        Ok(self.todo_manager.resolve_reference(
+            reference,
+            &current_user.id,
+            self.auth_manager.current_context(),
+            self.config.default_sort,
+        )?)
+    }
+    
+    async fn register(&mut self, args: RegisterArgs) -> Result<()> {
+        if let RegisterArgs { username: Some(username), email: Some(email), password: Some(password) } = &args {
+            // Provisioning: nobody is there to read a friendly message, so
+            // failures must reach the exit status
+            self.auth_manager.register(username, email, password).await?;
+            self.note(format!("{} Registered {}", self.icons.success.green(), username.bright_green()));
+            return Ok(());
+        }
+        if !std::io::stdin().is_terminal() {
+            let missing: Vec<&str> = [
+                (args.username.is_none(), "--username (TODO_REG_USERNAME)"),
+                (args.email.is_none(), "--email (TODO_REG_EMAIL)"),
+                (args.password.is_none(), "--password (TODO_REG_PASSWORD)"),
+            ].iter().filter(|(missing, _)| *missing).map(|(_, name)| *name).collect();
+            return Err(UsageError(format!("Registering without a terminal needs {}", missing.join(", "))).into());
+        }
+        
+        println!("{}", label(&self.icons.app, "Welcome to Todo CLI - Registration").bright_cyan().bold());
+        
+        let username: String = match args.username {
+            Some(username) => username,
+            None => Input::new()
+                .with_prompt("Username")
+                .interact_text()?,
+        };
             
-        let email: String = Input::new()
-            .with_prompt("Email")
-            .interact_text()?;
+        let email: String = match args.email {
+            Some(email) => email,
+            None => Input::new()
+                .with_prompt("Email")
+                .interact_text()?,
+        };
             
-        let password = Password::new()
-            .with_prompt("Password")
-            .with_confirmation("Confirm password", "Passwords don't match")
-            .interact()?;
+        let password = match args.password {
+            Some(password) => password,
+            None => Password::new()
+                .with_prompt("Password")
+                .with_confirmation("Confirm password", "Passwords don't match")
+                .interact()?,
+        };
             
         match self.auth_manager.register(&username, &email, &password).await {
             Ok(_) => {
-                println!("{} Registration successful! You can now login.", "✅".green());
+                self.note(format!("{} Registration successful! You can now login.", self.icons.success.green()));
             },
             Err(e) => {
-                println!("{} Registration failed: {}", "❌".red(), e);
+                println!("{} Registration failed: {}", self.icons.error.red(), e);
             }
         }
         
@@ -174,7 +1103,7 @@ impl TodoApp {
     }
     
     async fn login(&mut self) -> Result<()> {
-        println!("{}", "🔐 Login to Todo CLI".bright_blue().bold());
+        println!("{}", label(&self.icons.login, "Login to Todo CLI").bright_blue().bold());
         
         let username: String = Input::new()
             .with_prompt("Username")
@@ -185,44 +1114,169 @@ impl TodoApp {
             .interact()?;
             
         match self.auth_manager.login(&username, &password).await {
-            Ok(user) => {
-                println!("{} Welcome back, {}! 👋", "✅".green(), user.username.bright_green());
-                self.check_reminders().await?;
+            Ok((user, failed)) => {
+                self.note(format!("{} Welcome back, {}! {}", self.icons.success.green(), user.username.bright_green(), self.icons.wave));
+                self.apply_user_settings();
+                if let (true, Some(last_at)) = (self.config.failed_login_notice, failed.last_at) {
+                    // Not `note`: --quiet shouldn't hide a possible break-in attempt
+                    println!("{} {} since your last login, the latest on {}",
+                        self.icons.warning.yellow(),
+                        pluralize(failed.count as i64, "failed login attempt"),
+                        last_at.with_timezone(&Local).format(self.config.display_format()));
+                }
+                self.check_reminders(Some(self.config.max_reminders_shown)).await?;
             },
             Err(e) => {
-                println!("{} Login failed: {}", "❌".red(), e);
+                println!("{} Login failed: {}", self.icons.error.red(), e);
             }
         }
         
         Ok(())
     }
     
+    fn forgot_username(&self, email: &str) {
+        if !self.config.reveal_username_by_email {
+            // Same answer either way, so this can't be used to probe for accounts
+            println!("{} If an account uses {}, its username isn't shown here (reveal_username_by_email is off)", self.icons.info.blue(), email.trim());
+            return;
+        }
+        match self.auth_manager.find_by_email(email) {
+            Some(user) => println!("{} The account for {} is: {}", self.icons.success.green(), user.email, user.username.bright_green()),
+            None => println!("{} No account uses {}", self.icons.info.blue(), email.trim()),
+        }
+    }
+    
     async fn logout(&mut self) -> Result<()> {
         self.auth_manager.logout().await?;
-        println!("{} Logged out successfully! 👋", "✅".green());
+        self.apply_user_settings();
+        self.note(format!("{} Logged out successfully! {}", self.icons.success.green(), self.icons.wave));
         Ok(())
     }
     
-    fn ensure_authenticated(&self) -> Result<()> {
-        if !self.auth_manager.is_authenticated() {
-            println!("{} Please login first using: todo login", "❌".red());
-            std::process::exit(1);
+    /// Asks for confirmation when `enabled` and we are attached to a terminal;
+    /// otherwise proceeds without prompting.
+    fn confirm(&self, enabled: bool, prompt: &str) -> Result<bool> {
+        if !enabled || !std::io::stdin().is_terminal() {
+            return Ok(true);
+        }
+        
+        Ok(Confirm::new()
+            .with_prompt(prompt)
+            .default(false)
+            .interact()?)
+    }
+    
+    /// Lists pending todos that look like `title` and asks whether to add it
+    /// anyway. Without a terminal the warning is shown and the add goes ahead.
+    fn confirm_not_duplicate(&self, user_id: &str, title: &str) -> Result<bool> {
+        let similar = self.todo_manager.find_similar(user_id, title, self.config.duplicate_threshold);
+        if similar.is_empty() {
+            return Ok(true);
+        }
+        
+        eprintln!("{} Similar pending todos:", self.icons.warning.yellow());
+        for (score, todo) in &similar {
+            eprintln!("   {} {} ({:.0}% alike)", self.short_id(&todo.id).bright_black(), todo.title, score * 100.0);
+        }
+        self.confirm(true, "Add it anyway?")
+    }
+    
+    fn print_first_run_hint(&self) {
+        println!("{}", label(&self.icons.wave, "No accounts yet. Create one with: todo register").bright_cyan());
+    }
+    
+    /// With `single_user_auto_login`, starts a session for the only account
+    /// when there is no valid one.
+    fn try_auto_login(&mut self) -> Result<()> {
+        if !self.config.single_user_auto_login || self.auth_manager.is_authenticated() {
+            return Ok(());
+        }
+        if let Some(user) = self.auth_manager.auto_login()? {
+            self.apply_user_settings();
+            if self.verbosity == Verbosity::Verbose {
+                eprintln!("[verbose] signed in automatically as {}", user.username);
+            }
         }
         Ok(())
     }
     
-    async fn add_todo(&mut self, title: Option<String>, description: Option<String>, priority: Option<String>, due_date: Option<String>) -> Result<()> {
+    /// Fails with the `AuthError` saying why nobody is signed in: no accounts
+    /// at all, no session, or one that expired. With `login_on_expiry` an
+    /// expired session gets a login prompt first.
+    async fn ensure_authenticated(&mut self) -> Result<()> {
+        if !self.auth_manager.has_users() {
+            return Err(AuthError::NoAccounts.into());
+        }
+        self.try_auto_login()?;
+        match self.auth_manager.get_current_user() {
+            Ok(_) => {},
+            Err(AuthError::SessionExpired | AuthError::SessionIdle)
+                if self.config.login_on_expiry && std::io::stdin().is_terminal() => {
+                println!("{} Your session expired, please log in again", self.icons.warning.yellow());
+                self.login().await?;
+                self.auth_manager.get_current_user()?;
+            },
+            Err(e) => return Err(e.into()),
+        }
+        Ok(self.auth_manager.touch_session()?)
+    }
+    
+    async fn add_todo(&mut self, mut args: AddArgs) -> Result<()> {
         let current_user = self.auth_manager.get_current_user()?;
         
-        let title = match title {
-            Some(t) => t,
-            None => Input::new()
+        if let Some(ref key) = args.key {
+            if !args.upsert && self.todo_manager.find_by_key(&current_user.id, key).is_some() {
+                return Err(TodoError::DuplicateKey(key.clone()).into());
+            }
+        }
+        // Without a terminal nobody can answer prompts, so optional fields
+        // that weren't passed stay empty
+        let interactive = std::io::stdin().is_terminal();
+        
+        // Validate before prompting so a typo doesn't waste the answers
+        let mut offsets = args.remind_before.iter()
+            .map(|offset| dates::parse_offset(offset))
+            .collect::<dates::Result<Vec<_>>>()?;
+        offsets.sort_unstable();
+        offsets.dedup();
+        let estimate = args.estimate.as_deref().map(dates::parse_estimate).transpose()?;
+        let recurrence = args.every.as_deref().map(Recurrence::from_string).transpose()?;
+        
+        let title = match args.title {
+            Some(t) => todo::validate_title(&t)?,
+            None if !interactive => return Err(TodoError::EmptyTitle.into()),
+            None => todo::validate_title(&Input::<String>::new()
                 .with_prompt("Todo title")
-                .interact_text()?,
+                .validate_with(|input: &String| title_prompt_check(input))
+                .interact_text()?)?,
+        };
+        // Flags win over inline tokens when both give a field
+        let title = if args.quick || (self.config.quick_add && !args.literal) {
+            let quick = QuickAdd::parse(&title);
+            if let Some(priority) = quick.priority {
+                args.priority.get_or_insert(priority);
+            }
+            if let Some(due) = quick.due {
+                let day = dates::parse_day(&due, self.config.date_order, Local::now().date_naive())?;
+                args.due_date.get_or_insert(day.format("%Y-%m-%d").to_string());
+            }
+            if args.project.is_none() {
+                args.project = quick.project;
+            }
+            args.tags.extend(quick.tags);
+            todo::validate_title(&quick.title)?
+        } else {
+            title
         };
         
-        let description = match description {
+        if args.check_duplicates && !self.confirm_not_duplicate(&current_user.id, &title)? {
+            self.note(format!("{} Not added", self.icons.info.blue()));
+            return Ok(());
+        }
+        
+        let description = match args.description {
             Some(d) => Some(d),
+            None if !interactive => None,
             None => {
                 let desc: String = Input::new()
                     .with_prompt("Description (optional)")
@@ -232,13 +1286,19 @@ impl TodoApp {
             }
         };
         
-        let priority = match priority {
+        let priority = match args.priority {
             Some(p) => Priority::from_string(&p)?,
+            None if !interactive => self.config.default_priority.clone(),
             None => {
                 let priorities = ["Low", "Medium", "High"];
+                let default = match self.config.default_priority {
+                    Priority::Low => 0,
+                    Priority::Medium => 1,
+                    Priority::High => 2,
+                };
                 let selection = Select::new()
                     .with_prompt("Priority")
-                    .default(1)
+                    .default(default)
                     .items(&priorities)
                     .interact()?;
                 match selection {
@@ -250,87 +1310,224 @@ impl TodoApp {
             }
         };
         
-        let due_date = match due_date {
-            Some(d) => Some(chrono::NaiveDateTime::parse_from_str(&format!("{} 23:59:59", d), "%Y-%m-%d %H:%M:%S")?),
+        let due_tz = args.tz.as_deref().map(dates::parse_tz).transpose()?;
+        let due_date = match args.due_date {
+            Some(d) => {
+                let due = parse_due_date(&d, self.config.date_order)?;
+                if !args.force && dates::is_past(due, due_tz.as_deref(), Local::now()) {
+                    eprintln!("{} Due date {} is in the past (use --force to silence this)", self.icons.warning.yellow(), d.trim());
+                }
+                Some(due)
+            },
+            None if self.config.require_due_date && !interactive => {
+                return Err(UsageError(
+                    "A due date is required; pass --due-date (or set \"require_due_date\": false in config.json)".to_string()
+                ).into());
+            },
+            None if !interactive => None,
             None => {
-                let date_str: String = Input::new()
-                    .with_prompt("Due date (YYYY-MM-DD, optional)")
-                    .allow_empty(true)
-                    .interact_text()?;
-                if date_str.is_empty() {
-                    None
-                } else {
-                    Some(chrono::NaiveDateTime::parse_from_str(&format!("{} 23:59:59", date_str), "%Y-%m-%d %H:%M:%S")?)
+                let required = self.config.require_due_date;
+                loop {
+                    let date_str: String = Input::new()
+                        .with_prompt(if required { "Due date (YYYY-MM-DD)" } else { "Due date (YYYY-MM-DD, optional)" })
+                        .allow_empty(!required)
+                        .interact_text()?;
+                    if date_str.is_empty() {
+                        break None;
+                    }
+                    
+                    let due = parse_due_date(&date_str, self.config.date_order)?;
+                    // Backdating is occasionally intended, so ask rather than refuse
+                    if !args.force && dates::is_past(due, due_tz.as_deref(), Local::now()) && !Confirm::new()
+                        .with_prompt("That due date is in the past — continue?")
+                        .default(false)
+                        .interact()? {
+                        continue;
+                    }
+                    break Some(due);
                 }
             }
         };
         
-        let todo = Todo::new(title, description, priority, due_date, current_user.id.clone());
-        self.todo_manager.add_todo(todo.clone()).await?;
-        
-        println!("{} Todo added successfully!", "✅".green());
-        self.print_todo(&todo);
+        let due_date = match due_date {
+            Some(due) if args.business_days || self.config.skip_weekends => {
+                let moved = dates::skip_weekend(due);
+                if moved != due {
+                    self.note(format!("{} {} is a weekend day; due {} instead", self.icons.info.blue(), due.format("%A"), moved.format("%A %Y-%m-%d")));
+                }
+                Some(moved)
+            },
+            due => due,
+        };
+        
+        if recurrence.is_some() && due_date.is_none() {
+            return Err(UsageError("A repeating todo needs a due date to count from; pass --due-date".to_string()).into());
+        }
+        if due_tz.is_some() && due_date.is_none() {
+            return Err(UsageError("--tz says which time zone the due date is in; pass --due-date too".to_string()).into());
+        }
+        
+        let mut todo = Todo::new(title, description, priority, due_date, current_user.id.clone());
+        todo.external_key = args.key;
+        todo.reminder_offsets = offsets;
+        todo.estimate_minutes = estimate;
+        todo.recurrence = recurrence;
+        todo.due_tz = due_tz;
+        todo.project = args.project
+            .or_else(|| self.auth_manager.current_context().map(str::to_string))
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        for tag in args.tags.iter().map(|t| todo::normalize_tag(t)) {
+            if !tag.is_empty() && !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        }
+        
+        if args.upsert {
+            let (todo, created) = self.todo_manager.upsert_by_key(todo).await?;
+            if created {
+                self.note(format!("{} Todo added successfully!", self.icons.success.green()));
+            } else {
+                self.note(format!("{} Todo updated successfully!", self.icons.success.green()));
+            }
+            self.print_todo(&todo);
+            return Ok(());
+        }
+        
+        self.todo_manager.add_todo(todo.clone()).await?;
+        
+        self.note(format!("{} Todo added successfully!", self.icons.success.green()));
+        self.print_todo(&todo);
         
         Ok(())
     }
     
-    async fn list_todos(&self, status_filter: Option<String>, priority_filter: Option<String>) -> Result<()> {
-        let current_user = self.auth_manager.get_current_user()?;
+    async fn list_todos(&self, args: ListArgs) -> Result<()> {
+        let current_user = self.subject()?;
         let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        // Positions are only shown in the view they resolve against
+        let numbered = args.view.sort.is_none() && !args.all_projects && !args.actionable && !args.by_tag && self.viewing.is_none();
+        let sort_key = self.sort_key(args.view.sort)?;
+        let mut filter = args.filter.to_filter()?;
+        let numbered = numbered && filter.is_empty();
+        // Your project context means nothing for someone else's todos
+        if filter.project.is_none() && !args.all_projects && self.viewing.is_none() {
+            filter.project = self.auth_manager.current_context().map(str::to_string);
+        }
         
-        let filtered_todos: Vec<&Todo> = todos.iter()
-            .filter(|todo| {
-                if let Some(ref status) = status_filter {
-                    let filter_status = Status::from_string(status).unwrap_or(Status::Pending);
-                    todo.status == filter_status
-                } else {
-                    true
-                }
-            })
-            .filter(|todo| {
-                if let Some(ref priority) = priority_filter {
-                    let filter_priority = Priority::from_string(priority).unwrap_or(Priority::Medium);
-                    todo.priority == filter_priority
-                } else {
-                    true
-                }
-            })
+        let mut filtered_todos: Vec<&Todo> = todos.iter()
+            .filter(|todo| filter.matches(todo))
             .collect();
         
+        // Tally what --actionable hides so the footer can say why
+        let mut hidden: BTreeMap<Blocker, usize> = BTreeMap::new();
+        if args.actionable {
+            let now = Local::now();
+            filtered_todos.retain(|todo| match todo.blocker(now) {
+                Some(blocker) => {
+                    *hidden.entry(blocker).or_default() += 1;
+                    false
+                },
+                None => true,
+            });
+        }
+        
+        if args.view.count {
+            println!("{}", filtered_todos.len());
+            return Ok(());
+        }
+        
+        sort_todos(&mut filtered_todos, sort_key);
+        
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&filtered_todos)?);
+            return Ok(());
+        }
+        
         if filtered_todos.is_empty() {
-            println!("{} No todos found!", "ℹ️".blue());
+            self.note(format!("{} No todos found!", self.icons.info.blue()));
             return Ok(());
         }
         
-        println!("\n{}", "📋 Your Todos".bright_cyan().bold());
-        println!("{}", "─".repeat(80).bright_black());
+        let heading = match &self.viewing {
+            Some(user) => format!("{}'s Todos", user.username),
+            None => "Your Todos".to_string(),
+        };
+        self.note(format!("\n{}", label(&self.icons.list, &heading).bright_cyan().bold()));
+        self.note(format!("{}", self.icons.rule.repeat(80).bright_black()));
+        
+        let oneline = args.oneline || (!args.detailed && self.config.list_format == ListFormat::Oneline);
+        if args.by_tag {
+            let (groups, untagged) = todo::group_by_tag(&filtered_todos);
+            let mut sections: Vec<(String, Vec<&Todo>)> = groups.into_iter()
+                .map(|(tag, todos)| (format!("#{}", tag), todos))
+                .collect();
+            if !untagged.is_empty() {
+                sections.push(("No tags".to_string(), untagged));
+            }
+            for (heading, todos) in sections {
+                let completed = todos.iter().filter(|t| t.status == Status::Completed).count();
+                println!("\n{} {} {}",
+                    self.icons.tags,
+                    heading.bright_cyan().bold(),
+                    format!("({} pending, {} completed)", todos.len() - completed, completed).bright_black());
+                for todo in todos {
+                    if oneline {
+                        self.print_todo_oneline(todo);
+                    } else {
+                        self.print_todo(todo);
+                        println!();
+                    }
+                }
+            }
+            return Ok(());
+        }
         
-        for todo in filtered_todos {
+        let number_width = format!("{}.", filtered_todos.len()).len();
+        for (i, todo) in filtered_todos.iter().enumerate() {
+            if oneline {
+                if numbered {
+                    print!("{} ", format!("{:>width$}", format!("{}.", i + 1), width = number_width).bright_black());
+                }
+                self.print_todo_oneline(todo);
+                continue;
+            }
+            if numbered {
+                print!("{} ", format!("{}.", i + 1).bright_black());
+            }
             self.print_todo(todo);
             println!();
         }
         
+        if !hidden.is_empty() {
+            let summary: Vec<String> = hidden.iter()
+                .map(|(blocker, count)| format!("{} {}", count, blocker.label()))
+                .collect();
+            self.note(format!("{}", format!("Not actionable: {}", summary.join(", ")).bright_black()));
+        }
+        
         Ok(())
     }
     
-    async fn complete_todo(&mut self, id: Option<String>) -> Result<()> {
+    async fn complete_todo(&mut self, id: Option<String>, note: Option<String>) -> Result<()> {
         let current_user = self.auth_manager.get_current_user()?;
         
         let todo_id = match id {
-            Some(id) => id,
+            Some(id) => self.resolve_id(&id)?,
             None => {
                 let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
-                let pending_todos: Vec<&Todo> = todos.iter()
+                let mut pending_todos: Vec<&Todo> = todos.iter()
                     .filter(|t| t.status == Status::Pending)
                     .collect();
+                sort_todos(&mut pending_todos, self.config.default_sort);
                 
                 if pending_todos.is_empty() {
-                    println!("{} No pending todos found!", "ℹ️".blue());
+                    self.note(format!("{} No pending todos found!", self.icons.info.blue()));
                     return Ok(());
                 }
                 
                 let items: Vec<String> = pending_todos.iter()
-                    .map(|t| format!("{} - {}", t.id[..8].to_string(), t.title))
+                    .map(|t| format!("{} - {}", self.short_id(&t.id), t.title))
                     .collect();
                 
                 let selection = Select::new()
@@ -342,8 +1539,151 @@ impl TodoApp {
             }
         };
         
-        self.todo_manager.complete_todo(&todo_id).await?;
-        println!("{} Todo completed! 🎉", "✅".green());
+        let todo = self.todo_manager.get_todo(&todo_id).await?;
+        if todo.status == Status::Completed && todo.is_visible_to(&current_user.id) {
+            self.note(format!("{} '{}' is already completed", self.icons.info.blue(), todo.title));
+            return Ok(());
+        }
+        
+        // Completing a repeating todo archives it when the config says so
+        let archives = todo.next_occurrence().is_some() && self.config.archive_completed_recurrences;
+        let confirmed = if archives {
+            self.confirm(self.config.confirmations.complete || self.config.confirmations.archive, "Complete and archive this todo?")?
+        } else {
+            self.confirm(self.config.confirmations.complete, "Mark this todo as completed?")?
+        };
+        if !confirmed {
+            self.note(format!("{} Cancelled", self.icons.info.blue()));
+            return Ok(());
+        }
+        
+        let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+        if self.todo_manager.complete_todo(&todo_id, &current_user.id, note.clone()).await? {
+            self.record_completion(&current_user.id, match note {
+                Some(note) => format!("{} — {}", todo.title, note),
+                None => todo.title.clone(),
+            })?;
+            self.note(format!("{} Todo completed! {}", self.icons.success.green(), self.icons.celebrate));
+        } else {
+            self.note(format!("{} '{}' is already completed", self.icons.info.blue(), todo.title));
+        }
+        
+        Ok(())
+    }
+    
+    /// Adds a "complete" entry to the history log for `todo history`.
+    fn record_completion(&self, user_id: &str, detail: String) -> Result<()> {
+        self.storage.append_history(&HistoryEntry {
+            at: chrono::Utc::now(),
+            user_id: user_id.to_string(),
+            action: "complete".to_string(),
+            detail,
+        })
+    }
+    
+    /// Shows what the file would add and asks first, unless `yes` is set or
+    /// there's no terminal to ask on.
+    async fn import_todos(&mut self, file: &std::path::Path, yes: bool) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        // A file the user picked, so a bad one is their input, not a broken store
+        let records = import::read_records(file)
+            .map_err(|e| UsageError(format!("{:#}", e)))?;
+        
+        // Check every record before adding any, so a bad file adds nothing
+        let todos = records.into_iter()
+            .enumerate()
+            .map(|(i, record)| {
+                record.into_todo(&current_user.id, self.config.date_order, &self.config.default_priority)
+                    .with_context(|| format!("Record {} in {}", i + 1, file.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        let (todos, duplicates) = self.todo_manager.split_duplicates(todos);
+        let skipped = pluralize(duplicates.len() as i64, "duplicate");
+        if todos.is_empty() {
+            self.note(format!("{} Nothing to import (skipped {})", self.icons.info.blue(), skipped));
+            return Ok(());
+        }
+        self.note(format!("{} Will import {}, skip {}", self.icons.info.blue(), pluralize(todos.len() as i64, "todo"), skipped));
+        if !yes && !self.confirm(true, "Import them?")? {
+            self.note(format!("{} Cancelled", self.icons.info.blue()));
+            return Ok(());
+        }
+        
+        let imported = self.todo_manager.add_todos(todos).await?;
+        self.note(format!("{} Imported {}, skipped {}", self.icons.success.green(), pluralize(imported as i64, "todo"), skipped));
+        
+        Ok(())
+    }
+    
+    /// Reads every line of stdin before running any, then prints one JSON
+    /// reply per request once the todos have been saved.
+    async fn run_batch(&mut self) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let lines = std::io::stdin().lines()
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(|e| UsageError(format!("Failed to read batch requests from stdin: {}", e)))?;
+        let context = batch::Context {
+            user_id: current_user.id.clone(),
+            project: self.auth_manager.current_context().map(str::to_string),
+            sort: self.config.default_sort,
+            date_order: self.config.date_order,
+            default_priority: self.config.default_priority.clone(),
+            skip_weekends: self.config.skip_weekends,
+        };
+        
+        let replies = batch::run(&mut self.todo_manager, context, lines).await?;
+        for reply in replies {
+            println!("{}", serde_json::to_string(&reply)?);
+        }
+        
+        Ok(())
+    }
+    
+    async fn log_done(&mut self, title: String) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let title = todo::validate_title(&title)?;
+        let todo = Todo::new(title, None, self.config.default_priority.clone(), None, current_user.id.clone());
+        let todo = self.todo_manager.log_completed(todo).await?;
+        
+        self.note(format!("{} Logged as done! {}", self.icons.success.green(), self.icons.celebrate));
+        self.print_todo(&todo);
+        
+        Ok(())
+    }
+    
+    async fn reopen_todo(&mut self, id: Option<String>) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        
+        let todo_id = match id {
+            Some(id) => self.resolve_id(&id)?,
+            None => {
+                let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+                let mut completed_todos: Vec<&Todo> = todos.iter()
+                    .filter(|t| t.status == Status::Completed)
+                    .collect();
+                sort_todos(&mut completed_todos, self.config.default_sort);
+                
+                if completed_todos.is_empty() {
+                    self.note(format!("{} No completed todos found!", self.icons.info.blue()));
+                    return Ok(());
+                }
+                
+                let items: Vec<String> = completed_todos.iter()
+                    .map(|t| format!("{} - {}", self.short_id(&t.id), t.title))
+                    .collect();
+                
+                let selection = Select::new()
+                    .with_prompt("Select todo to reopen")
+                    .items(&items)
+                    .interact()?;
+                
+                completed_todos[selection].id.clone()
+            }
+        };
+        
+        self.todo_manager.reopen_todo(&todo_id, &current_user.id).await?;
+        self.note(format!("{} Todo reopened", self.icons.success.green()));
         
         Ok(())
     }
@@ -352,17 +1692,23 @@ impl TodoApp {
         let current_user = self.auth_manager.get_current_user()?;
         
         let todo_id = match id {
-            Some(id) => id,
+            Some(id) => self.resolve_id(&id)?,
             None => {
                 let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+                // Shared todos can only be deleted by their owner
+                let mut sorted_todos: Vec<&Todo> = todos.iter()
+                    .filter(|t| t.user_id == current_user.id)
+                    .collect();
                 
-                if todos.is_empty() {
-                    println!("{} No todos found!", "ℹ️".blue());
+                if sorted_todos.is_empty() {
+                    self.note(format!("{} No todos found!", self.icons.info.blue()));
                     return Ok(());
                 }
                 
-                let items: Vec<String> = todos.iter()
-                    .map(|t| format!("{} - {}", t.id[..8].to_string(), t.title))
+                sort_todos(&mut sorted_todos, self.config.default_sort);
+                
+                let items: Vec<String> = sorted_todos.iter()
+                    .map(|t| format!("{} - {}", self.short_id(&t.id), t.title))
                     .collect();
                 
                 let selection = Select::new()
@@ -370,12 +1716,17 @@ impl TodoApp {
                     .items(&items)
                     .interact()?;
                 
-                todos[selection].id.clone()
+                sorted_todos[selection].id.clone()
             }
         };
         
-        self.todo_manager.delete_todo(&todo_id).await?;
-        println!("{} Todo deleted!", "✅".green());
+        if !self.confirm(self.config.confirmations.delete, "Delete this todo? This cannot be undone")? {
+            self.note(format!("{} Cancelled", self.icons.info.blue()));
+            return Ok(());
+        }
+        
+        self.todo_manager.delete_todo(&todo_id, &current_user.id).await?;
+        self.note(format!("{} Todo deleted!", self.icons.success.green()));
         
         Ok(())
     }
@@ -384,17 +1735,20 @@ impl TodoApp {
         let current_user = self.auth_manager.get_current_user()?;
         
         let todo_id = match id {
-            Some(id) => id,
+            Some(id) => self.resolve_id(&id)?,
             None => {
                 let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
                 
                 if todos.is_empty() {
-                    println!("{} No todos found!", "ℹ️".blue());
+                    self.note(format!("{} No todos found!", self.icons.info.blue()));
                     return Ok(());
                 }
                 
-                let items: Vec<String> = todos.iter()
-                    .map(|t| format!("{} - {}", t.id[..8].to_string(), t.title))
+                let mut sorted_todos: Vec<&Todo> = todos.iter().collect();
+                sort_todos(&mut sorted_todos, self.config.default_sort);
+                
+                let items: Vec<String> = sorted_todos.iter()
+                    .map(|t| format!("{} - {}", self.short_id(&t.id), t.title))
                     .collect();
                 
                 let selection = Select::new()
@@ -402,18 +1756,22 @@ impl TodoApp {
                     .items(&items)
                     .interact()?;
                 
-                todos[selection].id.clone()
+                sorted_todos[selection].id.clone()
             }
         };
         
         let mut todo = self.todo_manager.get_todo(&todo_id).await?;
+        if !todo.is_visible_to(&current_user.id) {
+            return Err(TodoError::NotFound.into());
+        }
         
         println!("Editing todo: {}", todo.title.bright_yellow());
         
-        let new_title: String = Input::new()
+        let new_title = todo::validate_title(&Input::<String>::new()
             .with_prompt("Title")
             .default(todo.title.clone())
-            .interact_text()?;
+            .validate_with(|input: &String| title_prompt_check(input))
+            .interact_text()?)?;
         
         let new_description: String = Input::new()
             .with_prompt("Description")
@@ -441,212 +1799,1714 @@ impl TodoApp {
             _ => Priority::Medium,
         };
         
+        let date_order = self.config.date_order;
+        let current_due = todo.due_date.map(|d| d.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+        let new_due: String = Input::new()
+            .with_prompt("Due date (empty to clear)")
+            .default(current_due.clone())
+            .allow_empty(true)
+            .validate_with(|input: &String| -> Result<(), String> {
+                if input.trim().is_empty() {
+                    return Ok(());
+                }
+                parse_due_date(input, date_order).map(|_| ()).map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        
         todo.title = new_title;
+        if new_due.trim().is_empty() {
+            todo.due_date = None;
+            todo.due_tz = None;
+        } else if new_due.trim() != current_due {
+            todo.due_date = Some(parse_due_date(&new_due, date_order)?);
+        }
         todo.description = if new_description.is_empty() { None } else { Some(new_description) };
         todo.priority = new_priority;
         todo.updated_at = chrono::Utc::now();
         
-        self.todo_manager.update_todo(todo).await?;
-        println!("{} Todo updated successfully!", "✅".green());
+        self.todo_manager.update_todo(todo, &current_user.id).await?;
+        self.note(format!("{} Todo updated successfully!", self.icons.success.green()));
         
         Ok(())
     }
     
-    async fn show_overdue(&self) -> Result<()> {
+    async fn modify_tags(&mut self, args: &TagArgs, add: bool) -> Result<()> {
         let current_user = self.auth_manager.get_current_user()?;
-        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let filter = args.filter.to_filter()?;
         
-        let overdue_todos: Vec<&Todo> = todos.iter()
-            .filter(|todo| {
-                todo.status == Status::Pending && 
-                todo.due_date.map_or(false, |due| {
-                    let due_datetime = DateTime::<Local>::from_naive_utc_and_offset(due, *Local::now().offset());
-                    due_datetime < Local::now()
-                })
-            })
-            .collect();
+        if args.ids.is_empty() && filter.is_empty() {
+            return Err(TodoError::NothingSelected("Pass todo ids or select with --status, --priority, --tag or --project").into());
+        }
         
-        if overdue_todos.is_empty() {
-            println!("{} No overdue todos! 🎉", "✅".green());
-            return Ok(());
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let mut targets: Vec<String> = Vec::new();
+        for reference in &args.ids {
+            let id = self.resolve_id(reference)?;
+            let todo = todos.iter()
+                .find(|t| t.id == id)
+                .ok_or(TodoError::NotFound)?;
+            if filter.matches(todo) {
+                targets.push(todo.id.clone());
+            }
+        }
+        if args.ids.is_empty() {
+            targets = todos.iter()
+                .filter(|t| filter.matches(t))
+                .map(|t| t.id.clone())
+                .collect();
         }
         
-        println!("\n{} {} Overdue Todos", "⚠️".red(), overdue_todos.len());
-        println!("{}", "─".repeat(80).bright_black());
+        let tag = vec![args.name.clone()];
+        let changed = if add {
+            self.todo_manager.bulk_modify_tags(&targets, &current_user.id, &tag, &[]).await?
+        } else {
+            self.todo_manager.bulk_modify_tags(&targets, &current_user.id, &[], &tag).await?
+        };
         
-        for todo in overdue_todos {
-            self.print_todo(todo);
-            println!();
+        let tag = todo::normalize_tag(&args.name);
+        if add {
+            self.note(format!("{} Tagged {} with #{}", self.icons.success.green(), pluralize(changed as i64, "todo"), tag));
+        } else {
+            self.note(format!("{} Removed #{} from {}", self.icons.success.green(), tag, pluralize(changed as i64, "todo")));
         }
         
         Ok(())
     }
     
-    async fn show_today(&self) -> Result<()> {
+    /// Lets the user tick several todos, then complete, delete or tag them all.
+    async fn bulk_actions(&mut self) -> Result<()> {
         let current_user = self.auth_manager.get_current_user()?;
         let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let mut sorted_todos: Vec<&Todo> = todos.iter().collect();
+        sort_todos(&mut sorted_todos, self.config.default_sort);
         
-        let today = Local::now().date_naive();
-        let today_todos: Vec<&Todo> = todos.iter()
-            .filter(|todo| {
-                todo.due_date.map_or(false, |due| due.date() == today)
-            })
-            .collect();
+        if sorted_todos.is_empty() {
+            self.note(format!("{} No todos found!", self.icons.info.blue()));
+            return Ok(());
+        }
         
-        if today_todos.is_empty() {
-            println!("{} No todos due today! 🎉", "ℹ️".blue());
+        let items: Vec<String> = sorted_todos.iter()
+            .map(|t| format!("{} - {}", self.short_id(&t.id), t.title))
+            .collect();
+        let picked = MultiSelect::new()
+            .with_prompt("Select todos (space to toggle, enter to confirm)")
+            .items(&items)
+            .interact()?;
+        if picked.is_empty() {
+            self.note(format!("{} Nothing selected", self.icons.info.blue()));
             return Ok(());
         }
+        let ids: Vec<String> = picked.iter().map(|&i| sorted_todos[i].id.clone()).collect();
         
-        println!("\n{} {} Todos Due Today", "📅".yellow(), today_todos.len());
-        println!("{}", "─".repeat(80).bright_black());
+        let actions = ["Complete", "Delete", "Add tag", "Cancel"];
+        let action = Select::new()
+            .with_prompt(format!("Apply to {}", pluralize(ids.len() as i64, "todo")))
+            .items(&actions)
+            .interact()?;
         
-        for todo in today_todos {
-            self.print_todo(todo);
-            println!();
+        match action {
+            0 => {
+                let completed = self.todo_manager.bulk_complete(&ids, &current_user.id).await?;
+                self.note(format!("{} Completed {}! {}", self.icons.success.green(), pluralize(completed as i64, "todo"), self.icons.celebrate));
+            },
+            1 => {
+                let prompt = format!("Delete {}? This cannot be undone", pluralize(ids.len() as i64, "todo"));
+                // Always ask here, even if single deletes are configured not to
+                if !self.confirm(true, &prompt)? {
+                    self.note(format!("{} Cancelled", self.icons.info.blue()));
+                    return Ok(());
+                }
+                let deleted = self.todo_manager.bulk_delete(&ids, &current_user.id).await?;
+                self.note(format!("{} Deleted {}!", self.icons.success.green(), pluralize(deleted as i64, "todo")));
+                if deleted < ids.len() {
+                    self.note(format!("{} Skipped {} shared with you (only the owner can delete)", self.icons.info.blue(), pluralize((ids.len() - deleted) as i64, "todo")));
+                }
+            },
+            2 => {
+                let tag: String = Input::new()
+                    .with_prompt("Tag")
+                    .interact_text()?;
+                let tag = todo::normalize_tag(&tag);
+                if tag.is_empty() {
+                    return Err(TodoError::EmptyField("Tag").into());
+                }
+                let changed = self.todo_manager.bulk_modify_tags(&ids, &current_user.id, std::slice::from_ref(&tag), &[]).await?;
+                self.note(format!("{} Tagged {} with #{}", self.icons.success.green(), pluralize(changed as i64, "todo"), tag));
+            },
+            _ => self.note(format!("{} Cancelled", self.icons.info.blue())),
         }
         
         Ok(())
     }
     
-    async fn check_reminders(&self) -> Result<()> {
-        let current_user = self.auth_manager.get_current_user()?;
-        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
-        
-        let reminders = self.reminder_service.get_reminders(&todos);
+    fn use_project(&mut self, project: Option<String>, clear: bool) -> Result<()> {
+        if clear {
+            self.auth_manager.set_context(None)?;
+            self.note(format!("{} Showing all projects again", self.icons.success.green()));
+            return Ok(());
+        }
         
-        if !reminders.is_empty() {
-            println!("\n{} You have {} reminders:", "🔔".bright_yellow(), reminders.len());
-            for reminder in reminders {
-                println!("  {} {}", reminder.emoji, reminder.message.bright_yellow());
-            }
-            println!();
+        match project.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+            Some(project) => {
+                self.auth_manager.set_context(Some(project.clone()))?;
+                self.note(format!("{} Now working in {}", self.icons.success.green(), project.bright_cyan()));
+            },
+            None => match self.auth_manager.current_context() {
+                Some(project) => println!("{}", label(&self.icons.project, project)),
+                None => self.note(format!("{} No project selected (todo use <project>)", self.icons.info.blue())),
+            },
         }
         
         Ok(())
     }
     
-    async fn show_status(&self) -> Result<()> {
-        if self.auth_manager.is_authenticated() {
-            let current_user = self.auth_manager.get_current_user()?;
-            let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
-            
-            let pending = todos.iter().filter(|t| t.status == Status::Pending).count();
-            let completed = todos.iter().filter(|t| t.status == Status::Completed).count();
-            let overdue = todos.iter().filter(|t| {
-                t.status == Status::Pending && 
-                t.due_date.map_or(false, |due| {
-                    let due_datetime = DateTime::<Local>::from_naive_utc_and_offset(due, *Local::now().offset());
-                    due_datetime < Local::now()
-                })
-            }).count();
-            
-            println!("\n{} User Status", "👤".bright_blue());
-            println!("Username: {}", current_user.username.bright_green());
-            println!("Email: {}", current_user.email.bright_blue());
-            println!("\n{} Todo Statistics", "📊".bright_cyan());
-            println!("Pending: {}", pending.to_string().yellow());
-            println!("Completed: {}", completed.to_string().green());
-            println!("Overdue: {}", overdue.to_string().red());
-            println!("Total: {}", todos.len().to_string().bright_white());
+    async fn reschedule(&mut self, filter: &FilterArgs, overdue: bool, by: &str, business_days: bool) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let now = Local::now();
+        let today = now.date_naive();
+        let deferral = dates::Deferral::parse(by, today)?;
+        let filter = filter.to_filter()?;
+        
+        if filter.is_empty() && !overdue {
+            return Err(TodoError::NothingSelected("Pick the todos to move with --overdue, --priority, --tag or --project").into());
+        }
+        
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let matching: Vec<&Todo> = todos.iter()
+            .filter(|t| t.status == Status::Pending && filter.matches(t))
+            .filter(|t| !overdue || t.is_overdue(now))
+            .collect();
+        let (dated, undated): (Vec<&Todo>, Vec<&Todo>) = matching.into_iter().partition(|t| t.due_date.is_some());
+        let targets: Vec<String> = dated.iter().map(|t| t.id.clone()).collect();
+        let wanted = targets.len();
+        
+        let business_days = business_days || self.config.skip_weekends;
+        let moved = self.todo_manager.bulk_defer(targets, &current_user.id, deferral, today, business_days).await?;
+        
+        if moved.is_empty() {
+            self.note(format!("{} No todos to reschedule", self.icons.info.blue()));
         } else {
-            println!("{} Not logged in", "❌".red());
+            // Each end shown on its own todo's clock, zone included
+            let due_day = |t: &Todo| t.due_date.map(|due| with_zone(due.format("%Y-%m-%d").to_string(), t.due_tz.as_deref()));
+            let earliest = moved.iter().filter(|t| t.due_at().is_some()).min_by_key(|t| t.due_at()).and_then(due_day);
+            let latest = moved.iter().filter(|t| t.due_at().is_some()).max_by_key(|t| t.due_at()).and_then(due_day);
+            let range = match (earliest, latest) {
+                (Some(first), Some(last)) if first == last => format!("all now due {}", first),
+                (Some(first), Some(last)) => format!("now due {} to {}", first, last),
+                _ => String::new(),
+            };
+            self.note(format!("{} Rescheduled {}, {}", self.icons.success.green(), pluralize(moved.len() as i64, "todo"), range.bright_blue()));
+        }
+        if wanted > moved.len() {
+            self.note(format!("{} Left {} alone: already due after that", self.icons.info.blue(), pluralize((wanted - moved.len()) as i64, "todo")));
+        }
+        if !undated.is_empty() {
+            self.note(format!("{} Skipped {} without a due date", self.icons.info.blue(), pluralize(undated.len() as i64, "todo")));
         }
         
         Ok(())
     }
     
-    async fn interactive_mode(&mut self) -> Result<()> {
-        println!("{}", "🚀 Welcome to Todo CLI".bright_cyan().bold());
+    async fn defer_todo(&mut self, id: Option<String>, by: &str, business_days: bool) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let today = Local::now().date_naive();
+        let deferral = dates::Deferral::parse(by, today)?;
         
-        if !self.auth_manager.is_authenticated() {
-            let options = ["Login", "Register", "Exit"];
-            let selection = Select::new()
-                .with_prompt("What would you like to do?")
-                .items(&options)
-                .interact()?;
+        let todo_id = match id {
+            Some(id) => self.resolve_id(&id)?,
+            None => {
+                let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+                let mut pending_todos: Vec<&Todo> = todos.iter()
+                    .filter(|t| t.status == Status::Pending)
+                    .collect();
+                sort_todos(&mut pending_todos, SortKey::Due);
                 
-            match selection {
-                0 => self.login().await?,
-                1 => self.register().await?,
-                2 => return Ok(()),
-                _ => return Ok(()),
-            }
-        }
-        
-        if self.auth_manager.is_authenticated() {
-            self.check_reminders().await?;
-            
-            loop {
-                let options = [
-                    "Add Todo", "List Todos", "Complete Todo", "Edit Todo", 
-                    "Delete Todo", "Show Overdue", "Show Today", "Status", "Logout", "Exit"
-                ];
+                if pending_todos.is_empty() {
+                    self.note(format!("{} No pending todos found!", self.icons.info.blue()));
+                    return Ok(());
+                }
+                
+                let items: Vec<String> = pending_todos.iter()
+                    .map(|t| format!("{} - {}", self.short_id(&t.id), t.title))
+                    .collect();
                 
                 let selection = Select::new()
-                    .with_prompt("What would you like to do?")
-                    .items(&options)
+                    .with_prompt("Select todo to defer")
+                    .items(&items)
                     .interact()?;
-                    
-                match selection {
-                    0 => self.add_todo(None, None, None, None).await?,
-                    1 => self.list_todos(None, None).await?,
-                    2 => self.complete_todo(None).await?,
-                    3 => self.edit_todo(None).await?,
-                    4 => self.delete_todo(None).await?,
-                    5 => self.show_overdue().await?,
-                    6 => self.show_today().await?,
-                    7 => self.show_status().await?,
-                    8 => {
-                        self.logout().await?;
-                        break;
-                    },
-                    9 => break,
-                    _ => break,
-                }
+                
+                pending_todos[selection].id.clone()
             }
+        };
+        
+        let business_days = business_days || self.config.skip_weekends;
+        let todo = self.todo_manager.defer_todo(&todo_id, &current_user.id, deferral, today, business_days).await?;
+        if let Some(due) = todo.due_date {
+            self.note(format!("{} '{}' is now due {}", self.icons.success.green(), todo.title, self.format_due(due, todo.due_tz.as_deref()).bright_blue()));
         }
         
         Ok(())
     }
     
-    fn print_todo(&self, todo: &Todo) {
-        let status_emoji = match todo.status {
-            Status::Pending => "⏳",
-            Status::Completed => "✅",
-        };
-        
-        let priority_emoji = match todo.priority {
-            Priority::Low => "🟢",
-            Priority::Medium => "🟡",
-            Priority::High => "🔴",
+    async fn set_waiting(&mut self, id: &str, on: Option<String>, clear: bool) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let id = self.resolve_id(id)?;
+        let waiting = if clear {
+            None
+        } else {
+            Some(on.map(|o| o.trim().to_string()).filter(|o| !o.is_empty()))
         };
         
-        let id_short = &todo.id[..8];
-        
-        println!("{} {} {} [{}] {}", 
-            status_emoji, 
-            priority_emoji,
-            id_short.bright_black(),
-            todo.title.bright_white().bold(),
-            if todo.status == Status::Completed { "✨" } else { "" }
-        );
-        
-        if let Some(description) = &todo.description {
-            println!("   📝 {}", description.bright_black());
-        }
-        
-        if let Some(due_date) = todo.due_date {
-            let due_datetime = DateTime::<Local>::from_naive_utc_and_offset(due_date, *Local::now().offset());
-            let is_overdue = due_datetime < Local::now() && todo.status == Status::Pending;
-            
-            if is_overdue {
-                println!("   ⚠️  Due: {} {}", due_datetime.format("%Y-%m-%d %H:%M").to_string().red(), "(OVERDUE)".red().bold());
-            } else {
-                println!("   📅 Due: {}", due_datetime.format("%Y-%m-%d %H:%M").to_string().bright_blue());
-            }
+        let todo = self.todo_manager.set_waiting(&id, &current_user.id, waiting).await?;
+        match &todo.waiting {
+            Some(waiting) => self.note(format!("{} '{}' is {}", self.icons.success.green(), todo.title, waiting_label(waiting.on.as_deref()))),
+            None => self.note(format!("{} '{}' is no longer waiting", self.icons.success.green(), todo.title)),
         }
         
-        println!("   🕒 Created: {}", todo.created_at.format("%Y-%m-%d %H:%M").to_string().bright_black());
+        Ok(())
     }
-}
\ No newline at end of file
+    
+    async fn set_priority(&mut self, level: &str, id: Option<String>) -> Result<()> {
+        let priority = Priority::from_string(level)?;
+        let current_user = self.auth_manager.get_current_user()?;
+        
+        let todo_id = match id {
+            Some(id) => self.resolve_id(&id)?,
+            None => {
+                let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+                let mut pending_todos: Vec<&Todo> = todos.iter()
+                    .filter(|t| t.status == Status::Pending && !t.archived)
+                    .collect();
+                sort_todos(&mut pending_todos, self.config.default_sort);
+                
+                if pending_todos.is_empty() {
+                    self.note(format!("{} No pending todos found!", self.icons.info.blue()));
+                    return Ok(());
+                }
+                
+                let items: Vec<String> = pending_todos.iter()
+                    .map(|t| format!("{} {} - {}", self.priority_icon(&t.priority), self.short_id(&t.id), t.title))
+                    .collect();
+                
+                let selection = Select::new()
+                    .with_prompt(format!("Select todo to make {:?} priority", priority))
+                    .items(&items)
+                    .interact()?;
+                
+                pending_todos[selection].id.clone()
+            }
+        };
+        
+        let (todo, previous) = self.todo_manager.set_priority(&todo_id, &current_user.id, priority).await?;
+        if previous == todo.priority {
+            self.note(format!("{} '{}' is already {:?} priority", self.icons.info.blue(), todo.title, todo.priority));
+        } else {
+            self.note(format!("{} '{}' is now {} {:?} priority (was {:?})", self.icons.success.green(), todo.title, self.priority_icon(&todo.priority), todo.priority, previous));
+        }
+        
+        Ok(())
+    }
+    
+    /// Lists the focus list without arguments; otherwise adds `id` to it, or
+    /// with `clear` removes it (or everything, without an id).
+    async fn focus(&mut self, id: Option<&str>, clear: bool) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        
+        match (id, clear) {
+            (None, false) => {
+                let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+                let today = Local::now().date_naive();
+                let mut focus: Vec<&Todo> = todos.iter().filter(|t| t.in_focus(&current_user.id, today)).collect();
+                if focus.is_empty() {
+                    self.note(format!("{} Nothing on today's focus list. Add a todo with `todo focus <id>`", self.icons.info.blue()));
+                    return Ok(());
+                }
+                sort_todos(&mut focus, self.config.default_sort);
+                // Still-open todos first; finished ones stay until tomorrow
+                focus.sort_by_key(|t| t.status == Status::Completed);
+                
+                let done = focus.iter().filter(|t| t.status == Status::Completed).count();
+                self.note(format!("\n{} Today's focus: {} of {} done", self.icons.focus, done, focus.len()));
+                self.note(format!("{}", self.icons.rule.repeat(80).bright_black()));
+                for todo in focus {
+                    self.print_todo(todo);
+                    println!();
+                }
+            },
+            (None, true) => {
+                let cleared = self.todo_manager.clear_focus(&current_user.id).await?;
+                self.note(format!("{} Cleared {} from today's focus list", self.icons.success.green(), pluralize(cleared as i64, "todo")));
+            },
+            (Some(id), clear) => {
+                let id = self.resolve_id(id)?;
+                let todo = self.todo_manager.set_focus(&id, &current_user.id, !clear, Local::now().date_naive()).await?;
+                if clear {
+                    self.note(format!("{} '{}' is off today's focus list", self.icons.success.green(), todo.title));
+                } else {
+                    self.note(format!("{} '{}' is on today's focus list", self.icons.success.green(), todo.title));
+                }
+            },
+        }
+        
+        Ok(())
+    }
+    
+    async fn toggle_someday(&mut self, id: &str) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let id = self.resolve_id(id)?;
+        
+        let todo = self.todo_manager.toggle_someday(&id, &current_user.id).await?;
+        if todo.someday {
+            self.note(format!("{} '{}' moved to someday/maybe (see `todo list --someday`)", self.icons.success.green(), todo.title));
+        } else {
+            self.note(format!("{} '{}' is back on your list", self.icons.success.green(), todo.title));
+        }
+        
+        Ok(())
+    }
+    
+    async fn share_todo(&mut self, id: &str, username: &str) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        
+        let target = self.auth_manager.find_by_username(username)
+            .cloned()
+            .ok_or(AuthError::UserNotFound)
+            .with_context(|| format!("No user named {}", username))?;
+        
+        let id = self.resolve_id(id)?;
+        self.todo_manager.share_todo(&id, &current_user.id, &target.id).await?;
+        self.note(format!("{} Todo shared with {}!", self.icons.success.green(), target.username.bright_green()));
+        
+        Ok(())
+    }
+    
+    async fn add_subtask(&mut self, id: &str, title: String) -> Result<()> {
+        if title.trim().is_empty() {
+            return Err(TodoError::EmptyField("Subtask title").into());
+        }
+        
+        let id = self.resolve_id(id)?;
+        let current_user = self.auth_manager.get_current_user()?;
+        self.todo_manager.add_subtask(&id, &current_user.id, title).await?;
+        self.note(format!("{} Subtask added!", self.icons.success.green()));
+        
+        Ok(())
+    }
+    
+    async fn toggle_subtask(&mut self, id: &str, number: usize) -> Result<()> {
+        if number == 0 {
+            return Err(TodoError::ZeroNumber("Subtask").into());
+        }
+        
+        let id = self.resolve_id(id)?;
+        let user_id = self.auth_manager.get_current_user()?.id;
+        let auto_complete = self.config.auto_complete_parent;
+        // Ticking the last subtask and completing the parent is saved as one write
+        let (done, completed) = self.todo_manager.with_batched_writes(|manager| Box::pin(async move {
+            let done = manager.toggle_subtask(&id, &user_id, number - 1).await?;
+            let mut completed = None;
+            if done && auto_complete {
+                let todo = manager.get_todo(&id).await?;
+                if todo.subtasks.iter().all(|s| s.done) && manager.complete_todo(&id, &user_id, None).await? {
+                    completed = Some(todo);
+                }
+            }
+            Ok((done, completed))
+        })).await?;
+        
+        if done {
+            self.note(format!("{} Subtask {} done!", self.icons.success.green(), number));
+        } else {
+            self.note(format!("{} Subtask {} reopened", self.icons.status_pending.yellow(), number));
+        }
+        
+        if let Some(todo) = completed {
+            let current_user = self.auth_manager.get_current_user()?;
+            self.record_completion(&current_user.id, format!("{} (all subtasks done)", todo.title))?;
+            self.note(format!("{} All subtasks done, so '{}' is completed too {}", self.icons.success.green(), todo.title, self.icons.celebrate));
+        }
+        
+        Ok(())
+    }
+    
+    async fn add_milestone(&mut self, id: &str, label: &str, date: &str) -> Result<()> {
+        if label.trim().is_empty() {
+            return Err(TodoError::EmptyField("Milestone label").into());
+        }
+        
+        let date = parse_due_date(date, self.config.date_order)?;
+        let id = self.resolve_id(id)?;
+        let todo = self.todo_manager.get_todo(&id).await?;
+        if todo.due_date.is_some_and(|due| date > due) {
+            eprintln!("{} That milestone is after the todo's due date", self.icons.warning.yellow());
+        }
+        
+        let current_user = self.auth_manager.get_current_user()?;
+        let number = self.todo_manager.add_milestone(&id, &current_user.id, label.trim().to_string(), date).await?;
+        self.note(format!("{} Milestone {} added!", self.icons.success.green(), number));
+        
+        Ok(())
+    }
+    
+    async fn toggle_milestone(&mut self, id: &str, number: usize) -> Result<()> {
+        if number == 0 {
+            return Err(TodoError::ZeroNumber("Milestone").into());
+        }
+        
+        let id = self.resolve_id(id)?;
+        let current_user = self.auth_manager.get_current_user()?;
+        let milestone = self.todo_manager.toggle_milestone(&id, &current_user.id, number - 1).await?;
+        if milestone.done {
+            self.note(format!("{} Milestone '{}' done!", self.icons.success.green(), milestone.label));
+        } else {
+            self.note(format!("{} Milestone '{}' reopened", self.icons.status_pending.yellow(), milestone.label));
+        }
+        
+        Ok(())
+    }
+    
+    async fn show_overdue(&self, args: ViewArgs) -> Result<()> {
+        let current_user = self.subject()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let sort_key = self.sort_key(args.sort)?;
+        
+        let mut overdue_todos: Vec<&Todo> = todos.iter()
+            .filter(|todo| {
+                !todo.someday && todo.is_overdue(Local::now())
+            })
+            .collect();
+        
+        if args.count {
+            println!("{}", overdue_todos.len());
+            return Ok(());
+        }
+        
+        sort_todos(&mut overdue_todos, sort_key);
+        
+        if overdue_todos.is_empty() {
+            self.note(format!("{} No overdue todos! {}", self.icons.success.green(), self.icons.celebrate));
+            return Ok(());
+        }
+        
+        self.note(format!("\n{} {} Overdue Todos", self.icons.warning.red(), overdue_todos.len()));
+        self.note(format!("{}", self.icons.rule.repeat(80).bright_black()));
+        
+        for todo in overdue_todos {
+            self.print_todo(todo);
+            println!();
+        }
+        
+        Ok(())
+    }
+    
+    async fn show_completed(&self, period: &str) -> Result<()> {
+        let period = Period::from_string(period)?;
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let first_day = period.first_day(Local::now().date_naive(), self.config.week_start);
+        let since = dates::start_of_day(first_day);
+        
+        let mut completed: Vec<&Todo> = todos.iter()
+            .filter(|todo| todo.completed_time().is_some_and(|at| at >= since))
+            .collect();
+        completed.sort_by_key(|todo| todo.completed_time());
+        
+        let heading = match period {
+            Period::Today => "today".to_string(),
+            Period::Week | Period::Month => format!("since {}", first_day.format("%a %Y-%m-%d")),
+        };
+        if completed.is_empty() {
+            self.note(format!("{} Nothing completed {} yet", self.icons.info.blue(), heading));
+            return Ok(());
+        }
+        
+        self.note(format!("\n{} {} completed {}", self.icons.status_completed.green(), pluralize(completed.len() as i64, "todo"), heading));
+        self.note(format!("{}", self.icons.rule.repeat(80).bright_black()));
+        
+        for todo in completed {
+            self.print_todo(todo);
+            println!();
+        }
+        
+        Ok(())
+    }
+    
+    async fn show_plan(&self) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let now = Local::now();
+        let today = now.date_naive();
+        
+        // Waiting or snoozed todos can't be worked on, so they stay out of the plan
+        let mut plan: Vec<&Todo> = todos.iter()
+            .filter(|todo| todo.is_actionable(now))
+            .filter(|todo| todo.due_day().is_some_and(|day| day <= today))
+            .collect();
+        if plan.is_empty() {
+            self.note(format!("{} Nothing due today or overdue {}", self.icons.success.green(), self.icons.celebrate));
+            return Ok(());
+        }
+        todo::plan_order(&mut plan, now);
+        
+        let total: i64 = plan.iter().filter_map(|t| t.estimate_minutes).sum();
+        self.note(format!("\n{} Plan for today: {}{}",
+            self.icons.list.bright_cyan(),
+            pluralize(plan.len() as i64, "todo"),
+            if total > 0 { format!(", about {}", dates::format_minutes(total)) } else { String::new() }));
+        self.note(format!("{}", self.icons.rule.repeat(80).bright_black()));
+        
+        let mut elapsed = 0;
+        for (i, todo) in plan.iter().enumerate() {
+            let priority = match todo.priority {
+                Priority::Low => &self.icons.priority_low,
+                Priority::Medium => &self.icons.priority_medium,
+                Priority::High => &self.icons.priority_high,
+            };
+            let timing = match todo.estimate_minutes {
+                Some(minutes) => {
+                    elapsed += minutes;
+                    format!("{} {} (total {})", self.icons.estimate, dates::format_minutes(minutes), dates::format_minutes(elapsed))
+                },
+                None => format!("{} ?", self.icons.estimate),
+            };
+            println!("{:>3}. {} {} [{}]{}  {}",
+                i + 1,
+                priority,
+                self.short_id(&todo.id).bright_black(),
+                todo.title.bright_white().bold(),
+                if todo.is_overdue(now) { format!(" {}", "overdue".red()) } else { String::new() },
+                timing.bright_black());
+        }
+        
+        let unestimated = plan.iter().filter(|t| t.estimate_minutes.is_none()).count();
+        if unestimated > 0 {
+            self.note(format!("\n{} without an estimate (add one with --estimate)", pluralize(unestimated as i64, "todo")));
+        }
+        
+        Ok(())
+    }
+    
+    async fn search_todos(&self, query: &str, field: Option<&str>, regex: bool, args: ViewArgs) -> Result<()> {
+        let field = field.map(SearchField::from_string).transpose()?;
+        let search = Search::new(query, field, regex)?;
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let sort_key = self.sort_key(args.sort)?;
+        
+        let mut found: Vec<&Todo> = todos.iter()
+            .filter(|todo| search.matches(todo))
+            .collect();
+        
+        if args.count {
+            println!("{}", found.len());
+            return Ok(());
+        }
+        
+        if found.is_empty() {
+            self.note(format!("{} No todos match '{}'", self.icons.info.blue(), query));
+            return Ok(());
+        }
+        
+        sort_todos(&mut found, sort_key);
+        
+        self.note(format!("\n{} {} matching '{}'", self.icons.list.bright_cyan(), pluralize(found.len() as i64, "todo"), query));
+        self.note(format!("{}", self.icons.rule.repeat(80).bright_black()));
+        
+        for todo in found {
+            self.print_todo(todo);
+            println!();
+        }
+        
+        Ok(())
+    }
+    
+    async fn show_today(&self, args: ViewArgs) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let sort_key = self.sort_key(args.sort)?;
+        
+        let now = Local::now();
+        let today = now.date_naive();
+        let mut today_todos: Vec<&Todo> = todos.iter()
+            .filter(|todo| {
+                !todo.someday && todo.due_day() == Some(today)
+            })
+            .collect();
+        
+        if args.count {
+            println!("{}", today_todos.len());
+            return Ok(());
+        }
+        
+        sort_todos(&mut today_todos, sort_key);
+        
+        if today_todos.is_empty() {
+            self.note(format!("{} No todos due today! {}", self.icons.info.blue(), self.icons.celebrate));
+            return Ok(());
+        }
+        
+        self.note(format!("\n{} {} Todos Due Today", self.icons.calendar.yellow(), today_todos.len()));
+        self.note(format!("{}", self.icons.rule.repeat(80).bright_black()));
+        
+        for todo in today_todos {
+            self.print_todo(todo);
+            println!();
+        }
+        
+        Ok(())
+    }
+    
+    async fn show_calendar(&self, month: Option<&str>, day: Option<u32>) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let today = Local::now().date_naive();
+        let first = match month {
+            Some(month) => parse_month(month, today.year())?,
+            None => today.with_day(1).expect("day 1 exists"),
+        };
+        let next_month = first.checked_add_months(chrono::Months::new(1)).expect("month in range");
+        let selected = day
+            .map(|day| first.with_day(day).ok_or_else(|| UsageError(format!("{} has no day {}", first.format("%B %Y"), day))))
+            .transpose()?;
+        
+        // Pending todos per due day, read the same way as `today`
+        let mut due_on: BTreeMap<NaiveDate, Vec<&Todo>> = BTreeMap::new();
+        for todo in todos.iter().filter(|t| t.status == Status::Pending && !t.someday) {
+            if let Some(due) = todo.due_day().filter(|d| *d >= first && *d < next_month) {
+                due_on.entry(due).or_default().push(todo);
+            }
+        }
+        
+        println!("\n{}", label(&self.icons.calendar, &first.format("%B %Y").to_string()).bright_cyan().bold());
+        let week_start = self.config.week_start;
+        let mut weekday = week_start.first_day();
+        let mut header = String::new();
+        for _ in 0..7 {
+            header.push_str(&format!(" {:<5}", weekday));
+            weekday = weekday.succ();
+        }
+        println!("{}", header.trim_end().bright_black());
+        
+        let mut line = "      ".repeat((first - week_start.start_of_week(first)).num_days() as usize);
+        let mut date = first;
+        while date < next_month {
+            let count = due_on.get(&date).map_or(0, Vec::len);
+            // Fixed-width cell: day number plus an optional count, e.g. " 14·3 "
+            let text = if count > 0 {
+                format!("{:>3}{:<3}", date.day(), format!("·{}", count.min(99)))
+            } else {
+                format!("{:>3}   ", date.day())
+            };
+            let cell = if date == today {
+                text.reversed().bold().to_string()
+            } else if count > 0 && date < today {
+                text.red().bold().to_string()
+            } else if count > 0 {
+                text.yellow().to_string()
+            } else {
+                text
+            };
+            line.push_str(&cell);
+            if date.weekday() == week_start.last_day() {
+                println!("{}", line.trim_end());
+                line.clear();
+            }
+            date = date.succ_opt().expect("date in range");
+        }
+        if !line.is_empty() {
+            println!("{}", line.trim_end());
+        }
+        
+        let busy: usize = due_on.values().map(Vec::len).sum();
+        self.note(format!("\n{} due this month{}", pluralize(busy as i64, "todo"), if due_on.keys().any(|d| *d < today) { " (red days are overdue)" } else { "" }));
+        
+        if let Some(date) = selected {
+            let mut on_day = due_on.remove(&date).unwrap_or_default();
+            sort_todos(&mut on_day, self.config.default_sort);
+            
+            println!("\n{}", format!("Due {}", date.format("%A, %d %B")).bright_cyan().bold());
+            if on_day.is_empty() {
+                self.note(format!("{} Nothing due that day", self.icons.info.blue()));
+            }
+            for todo in on_day {
+                self.print_todo(todo);
+                println!();
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn dashboard(&mut self) -> Result<()> {
+        if !std::io::stdout().is_terminal() {
+            return Err(anyhow::anyhow!("The dashboard needs an interactive terminal"));
+        }
+        let current_user = self.auth_manager.get_current_user()?;
+        let id_length = self.config.id_length();
+        dashboard::run(&mut self.todo_manager, &current_user.id, self.config.default_sort, &self.icons, id_length).await
+    }
+    
+    /// Prints reminders, most urgent first. With a `limit`, only that many are
+    /// listed and the rest are summarized in one line.
+    async fn check_reminders(&self, limit: Option<usize>) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        
+        let reminders = self.reminder_service.get_reminders(&todos);
+        
+        if !reminders.is_empty() {
+            let shown = limit.filter(|&n| n > 0).unwrap_or(reminders.len()).min(reminders.len());
+            println!("\n{} You have {}:", self.icons.bell.bright_yellow(), pluralize(reminders.len() as i64, "reminder"));
+            for reminder in &reminders[..shown] {
+                println!("  {} {}", reminder.emoji, reminder.message.bright_yellow());
+            }
+            if shown < reminders.len() {
+                println!("  {}", format!("…and {} more (run `todo reminders`)", reminders.len() - shown).bright_black());
+            }
+            println!();
+        }
+        
+        Ok(())
+    }
+    
+    /// One line of counts, e.g. "2 critical, 3 warning, 5 info".
+    async fn reminder_summary(&self, json: bool) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let (critical, warning, info) = self.reminder_service.count_by_priority(&todos);
+        
+        if json {
+            println!("{}", serde_json::to_string_pretty(&ReminderCounts { critical, warning, info })?);
+        } else {
+            println!("{} critical, {} warning, {} info", critical, warning, info);
+        }
+        
+        Ok(())
+    }
+    
+    async fn acknowledge(&mut self, id: Option<String>) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = match id {
+            Some(reference) => {
+                let id = self.resolve_id(&reference)?;
+                let todo = self.todo_manager.get_todo(&id).await?;
+                if !todo.is_visible_to(&current_user.id) {
+                    return Err(TodoError::NotFound.into());
+                }
+                vec![todo]
+            },
+            None => self.todo_manager.get_user_todos(&current_user.id).await?,
+        };
+        
+        let acks: Vec<_> = todos.iter()
+            .filter_map(|todo| self.reminder_service.acknowledge(todo).map(|ack| (todo.id.clone(), ack)))
+            .collect();
+        if acks.is_empty() {
+            self.note(format!("{} Nothing to acknowledge", self.icons.info.blue()));
+            return Ok(());
+        }
+        
+        let count = self.todo_manager.acknowledge(acks).await?;
+        self.note(format!("{} Acknowledged {}", self.icons.success.green(), pluralize(count as i64, "reminder")));
+        
+        Ok(())
+    }
+    
+    async fn snooze_all(&mut self, snooze: Option<Snooze>) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let until = match snooze {
+            Some(Snooze::Hours(h)) => Some(chrono::Utc::now() + chrono::Duration::hours(h as i64)),
+            Some(Snooze::Until(day)) => {
+                let day = dates::parse_day(&day, self.config.date_order, Local::now().date_naive())?;
+                let start = day.and_hms_opt(0, 0, 0).unwrap()
+                    .and_local_timezone(Local)
+                    .earliest()
+                    .ok_or_else(|| anyhow::anyhow!("{} has no midnight in this time zone", day))?;
+                Some(start.with_timezone(&chrono::Utc))
+            },
+            None => None,
+        };
+        let count = self.todo_manager.snooze_all(&current_user.id, until).await?;
+        
+        match until {
+            Some(until) => self.note(format!("{} Snoozed {} until {}",
+                self.icons.success.green(),
+                pluralize(count as i64, "todo"),
+                until.with_timezone(&Local).format(self.config.display_format()))),
+            None if count == 0 => self.note(format!("{} Nothing is snoozed", self.icons.info.blue())),
+            None => self.note(format!("{} Unsnoozed {}", self.icons.success.green(), pluralize(count as i64, "todo"))),
+        }
+        
+        Ok(())
+    }
+    
+    async fn show_status(&self, json: bool, diagnostics: bool) -> Result<()> {
+        let diagnostics = diagnostics.then(|| self.diagnostics());
+        if !self.auth_manager.is_authenticated() {
+            if json {
+                println!("{}", serde_json::to_string_pretty(&StatusReport { authenticated: false, account: None, diagnostics })?);
+                return Ok(());
+            } else if !self.auth_manager.has_users() {
+                self.print_first_run_hint();
+            } else {
+                println!("{} Not logged in", self.icons.error.red());
+            }
+            if let Some(diagnostics) = diagnostics {
+                self.print_status_diagnostics(&diagnostics);
+            }
+            return Ok(());
+        }
+        
+        let current_user = self.subject()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let now = Local::now();
+        
+        // Someday and archived todos stay out of the counts, as they do in `overdue`
+        let in_play: Vec<&Todo> = todos.iter()
+            .filter(|t| t.status == Status::Pending && !t.someday && !t.archived)
+            .collect();
+        let pending = in_play.len();
+        let completed = todos.iter().filter(|t| t.status == Status::Completed).count();
+        let overdue = in_play.iter().filter(|t| t.is_overdue(now)).count();
+        let someday = todos.iter().filter(|t| t.status == Status::Pending && t.someday).count();
+        let pending_with = |priority: Priority| in_play.iter()
+            .filter(|t| t.priority == priority)
+            .count();
+        let (high, medium, low) = (pending_with(Priority::High), pending_with(Priority::Medium), pending_with(Priority::Low));
+        
+        if json {
+            let status = StatusReport {
+                authenticated: true,
+                account: Some(AccountStatus {
+                    username: current_user.username.clone(),
+                    email: current_user.email.clone(),
+                    pending,
+                    completed,
+                    overdue,
+                    someday,
+                    total: todos.len(),
+                    pending_by_priority: PriorityCounts { high, medium, low },
+                }),
+                diagnostics,
+            };
+            println!("{}", serde_json::to_string_pretty(&status)?);
+            return Ok(());
+        }
+        
+        let width = todos.len().to_string().len();
+        // Pad by display width so emoji labels line up with plain ones
+        let row = |name: &str, count: usize| {
+            let padding = 12usize.saturating_sub(console::measure_text_width(name));
+            format!("  {}{} {:>width$}", name, " ".repeat(padding), count, width = width)
+        };
+        
+        println!("\n{}", label(&self.icons.user, "User Status").bright_blue());
+        println!("  {:<12} {}", "Username", current_user.username.bright_green());
+        println!("  {:<12} {}", "Email", current_user.email.bright_blue());
+        
+        println!("\n{}", label(&self.icons.stats, "Todo Statistics").bright_cyan());
+        println!("{}", row("Pending", pending).yellow());
+        println!("{}", row("Completed", completed).green());
+        println!("{}", row("Overdue", overdue).red());
+        if someday > 0 {
+            println!("{}", row("Someday", someday).bright_black());
+        }
+        println!("{}", row("Total", todos.len()).bright_white());
+        println!("  {:<12} {}", "Done", self.progress_bar(completed, todos.len()));
+        
+        println!("\n  {}", "Pending by priority".bright_cyan());
+        println!("{}", row(&label(&self.icons.priority_high, "High"), high));
+        println!("{}", row(&label(&self.icons.priority_medium, "Medium"), medium));
+        println!("{}", row(&label(&self.icons.priority_low, "Low"), low));
+        
+        if let Some(diagnostics) = diagnostics {
+            self.print_status_diagnostics(&diagnostics);
+        }
+        
+        Ok(())
+    }
+    
+    fn diagnostics(&self) -> Diagnostics {
+        Diagnostics {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            data_dir: self.storage.data_dir().display().to_string(),
+            config_file: self.storage.config_file().display().to_string(),
+            files: self.storage.file_sizes().into_iter()
+                .map(|(path, bytes)| DataFile { path: path.display().to_string(), bytes })
+                .collect(),
+            total_todos: self.todo_manager.total_count(),
+        }
+    }
+    
+    fn print_status_diagnostics(&self, diagnostics: &Diagnostics) {
+        println!("\n{}", label(&self.icons.info, "Diagnostics").bright_cyan());
+        println!("  {:<14} {}", "Version", diagnostics.version);
+        println!("  {:<14} {}", "Data dir", diagnostics.data_dir);
+        println!("  {:<14} {}", "Config", diagnostics.config_file);
+        for file in &diagnostics.files {
+            let name = Path::new(&file.path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let size = match file.bytes {
+                Some(bytes) => format_size(bytes),
+                None => "not created yet".bright_black().to_string(),
+            };
+            println!("  {:<14} {}", name, size);
+        }
+        println!("  {:<14} {} (all users)", "Todos", diagnostics.total_todos);
+    }
+    
+    fn regenerate(&self, sort: Option<String>) -> Result<()> {
+        let sort_key = match sort {
+            Some(s) => SortKey::from_string(&s)?,
+            None => self.storage.markdown_sort(),
+        };
+        self.storage.regenerate_markdown_sorted(sort_key)?;
+        self.note(format!("{} Rebuilt todos.md", self.icons.success.green()));
+        Ok(())
+    }
+    
+    fn verify(&self, repair: bool) -> Result<()> {
+        let drift = self.storage.verify_markdown()?;
+        if drift.is_clean() {
+            self.note(format!("{} todos.md matches the stored data", self.icons.success.green()));
+            return Ok(());
+        }
+        
+        println!("{} todos.md is out of sync with the stored data", self.icons.warning.yellow());
+        for line in &drift.missing {
+            println!("  {} {}", "-".red(), line);
+        }
+        for line in &drift.unexpected {
+            println!("  {} {}", "+".yellow(), line);
+        }
+        println!("{} missing, {} unexpected",
+            pluralize(drift.missing.len() as i64, "line"),
+            pluralize(drift.unexpected.len() as i64, "line"));
+        
+        let repair = repair || (std::io::stdin().is_terminal() && Confirm::new()
+            .with_prompt("Rebuild todos.md from the stored data?")
+            .default(true)
+            .interact()?);
+        if repair {
+            self.storage.regenerate_markdown()?;
+            self.note(format!("{} Rebuilt todos.md", self.icons.success.green()));
+        }
+        
+        Ok(())
+    }
+    
+    fn show_leaderboard(&self) -> Result<()> {
+        if !self.config.leaderboard {
+            println!("{} The leaderboard is off. Set \"leaderboard\": true in config.json to enable it", self.icons.info.blue());
+            return Ok(());
+        }
+        
+        let today = Local::now().date_naive();
+        let week_start = self.config.week_start.start_of_week(today);
+        let since = dates::start_of_day(week_start);
+        
+        let counts = self.todo_manager.completed_counts_since(since);
+        let mut rows: Vec<(&str, usize)> = self.auth_manager.users()
+            .map(|user| (user.username.as_str(), counts.get(&user.id).copied().unwrap_or(0)))
+            .collect();
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        
+        let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(4);
+        self.note(format!("\n{}", label(&self.icons.trend, &format!("Completed since {}", week_start.format("%a %Y-%m-%d"))).bright_cyan()));
+        for (rank, (name, count)) in rows.iter().enumerate() {
+            println!("{:>3}. {:<name_width$}  {:>4}", rank + 1, name, count, name_width = name_width);
+        }
+        
+        Ok(())
+    }
+    
+    fn show_history(&self, limit: usize) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let entries = self.storage.read_history()?;
+        let mine: Vec<&HistoryEntry> = entries.iter()
+            .rev()
+            .filter(|e| e.user_id == current_user.id)
+            .take(limit)
+            .collect();
+        
+        if mine.is_empty() {
+            self.note(format!("{} No history yet", self.icons.info.blue()));
+            return Ok(());
+        }
+        
+        let width = mine.iter().map(|e| e.action.len()).max().unwrap_or(0);
+        for entry in mine {
+            println!("{}  {:<width$}  {}",
+                entry.at.with_timezone(&Local).format(self.config.display_format()).to_string().bright_black(),
+                entry.action.bright_cyan(),
+                entry.detail,
+                width = width);
+        }
+        Ok(())
+    }
+    
+    async fn prune(&mut self, archive: bool, dry_run: bool) -> Result<()> {
+        let admin = self.auth_manager.get_current_user()?;
+        if !admin.is_admin {
+            return Err(AuthError::AdminOnly("prune other users' todos").into());
+        }
+        let user_ids: HashSet<String> = self.auth_manager.users().map(|u| u.id.clone()).collect();
+        let orphans = self.todo_manager.orphans(&user_ids, !archive).len();
+        
+        if dry_run {
+            let action = if archive { "archive" } else { "delete" };
+            self.note(format!("{} Would {} {} without an owner account", self.icons.info.blue(), action, pluralize(orphans as i64, "todo")));
+            return Ok(());
+        }
+        let (enabled, verb) = if archive {
+            (self.config.confirmations.archive, "Archive")
+        } else {
+            (self.config.confirmations.delete, "Delete")
+        };
+        if orphans > 0 && !self.confirm(enabled, &format!("{} {} without an owner account?", verb, pluralize(orphans as i64, "todo")))? {
+            self.note(format!("{} Prune cancelled", self.icons.info.blue()));
+            return Ok(());
+        }
+        
+        let (orphans, shares) = self.todo_manager.prune_orphans(&user_ids, archive).await?;
+        if orphans == 0 && shares == 0 {
+            self.note(format!("{} Nothing to prune", self.icons.info.blue()));
+            return Ok(());
+        }
+        self.storage.append_history(&HistoryEntry {
+            at: chrono::Utc::now(),
+            user_id: admin.id.clone(),
+            action: "prune".to_string(),
+            detail: format!("{} orphaned todos {}, {} stale shares dropped", orphans, if archive { "archived" } else { "deleted" }, shares),
+        })?;
+        
+        let verb = if archive { "Archived" } else { "Deleted" };
+        self.note(format!("{} {} {} without an owner account and dropped {} to missing users",
+            self.icons.success.green(),
+            verb,
+            pluralize(orphans as i64, "todo"),
+            pluralize(shares as i64, "share")));
+        Ok(())
+    }
+    
+    async fn show_stats(&self, json: bool) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let todos = self.todo_manager.get_user_todos(&current_user.id).await?;
+        let report = StatsReport::compute(&todos, Local::now());
+        
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        
+        println!("\n{}", label(&self.icons.trend, "Productivity Stats").bright_cyan());
+        println!("Pending: {}", report.pending.to_string().yellow());
+        println!("Completed: {}", report.completed.to_string().green());
+        println!("Overdue: {}", report.overdue.to_string().red());
+        if report.someday > 0 {
+            println!("Someday: {}", report.someday.to_string().bright_black());
+        }
+        println!("Completion rate: {}", format!("{:.0}%", report.completion_rate * 100.0).bright_white());
+        println!("Streak: {} {}", pluralize(report.streak_days as i64, "day").bright_yellow(), self.icons.streak);
+        match report.avg_completion_hours {
+            Some(hours) => println!("Avg. time to complete: {}", format!("{:.1}h", hours).bright_blue()),
+            None => println!("Avg. time to complete: {}", "n/a".bright_black()),
+        }
+        
+        Ok(())
+    }
+    
+    async fn settings(&mut self, args: &SettingsArgs) -> Result<()> {
+        let current_user = self.auth_manager.get_current_user()?;
+        let mut settings = current_user.settings.clone();
+        let mut changed = args.reset;
+        
+        if args.reset {
+            settings = UserSettings::default();
+        }
+        if let Some(ref p) = args.default_priority {
+            settings.default_priority = Some(Priority::from_string(p)?);
+            changed = true;
+        }
+        if let Some(ref order) = args.date_order {
+            settings.date_order = Some(DateOrder::from_string(order)?);
+            changed = true;
+        }
+        if let Some(ref format) = args.date_format {
+            if !dates::is_valid_format(format) {
+                return Err(UsageError(format!("Invalid date format '{}'", format)).into());
+            }
+            settings.date_format = Some(format.clone());
+            changed = true;
+        }
+        for (days, name) in [(args.upcoming_days, "--upcoming-days"), (args.stale_days, "--stale-days")] {
+            if days.is_some_and(|d| d < 0) {
+                return Err(UsageError(format!("{} cannot be negative", name)).into());
+            }
+        }
+        if let Some(days) = args.upcoming_days {
+            settings.upcoming_days = Some(days);
+            changed = true;
+        }
+        if let Some(days) = args.stale_days {
+            settings.stale_days = Some(days);
+            changed = true;
+        }
+        
+        if changed {
+            self.auth_manager.update_settings(&current_user.id, settings.clone())?;
+            self.apply_user_settings();
+            self.note(format!("{} Settings saved!", self.icons.success.green()));
+        }
+        
+        let source = |set: bool| if set { "(yours)".bright_green() } else { "(global)".bright_black() };
+        println!("\n{}", label(&self.icons.user, &format!("Settings for {}", current_user.username)).bright_blue());
+        println!("Default priority: {:?} {}", self.config.default_priority, source(settings.default_priority.is_some()));
+        println!("Date order: {:?} {}", self.config.date_order, source(settings.date_order.is_some()));
+        println!("Date format: {} {}", self.config.date_format, source(settings.date_format.is_some()));
+        println!("Upcoming reminder window: {} {}", pluralize(self.config.reminders.upcoming_days, "day"), source(settings.upcoming_days.is_some()));
+        println!("Stale after: {} {}", pluralize(self.config.reminders.stale_days, "day"), source(settings.stale_days.is_some()));
+        
+        Ok(())
+    }
+    
+    async fn interactive_mode(&mut self, welcome: bool) -> Result<()> {
+        println!("{}", label(&self.icons.app, "Welcome to Todo CLI").bright_cyan().bold());
+        
+        let first_run = !self.auth_manager.has_users() && !self.storage.has_onboarded();
+        if (welcome || first_run) && std::io::stdin().is_terminal() {
+            self.onboarding().await?;
+        } else if !self.auth_manager.has_users() {
+            self.print_first_run_hint();
+        }
+        
+        self.try_auto_login()?;
+        if !self.auth_manager.is_authenticated() {
+            let options = ["Login", "Register", "Exit"];
+            let selection = Select::new()
+                .with_prompt("What would you like to do?")
+                .items(&options)
+                .interact()?;
+                
+            match selection {
+                0 => self.login().await?,
+                1 => self.register(RegisterArgs::default()).await?,
+                2 => return Ok(()),
+                _ => return Ok(()),
+            }
+        }
+        
+        if self.auth_manager.is_authenticated() {
+            self.check_reminders(Some(self.config.max_reminders_shown)).await?;
+            
+            // Once something handles SIGINT, Ctrl-C at a prompt comes back as
+            // an error instead of killing the process, so unsaved changes
+            // still get written on the way out
+            if !self.config.auto_save {
+                tokio::spawn(async {
+                    while tokio::signal::ctrl_c().await.is_ok() {}
+                });
+            }
+            
+            loop {
+                let mut options = vec![
+                    "Add Todo", "List Todos", "Complete Todo", "Reopen Todo", "Edit Todo", 
+                    "Delete Todo", "Bulk Actions", "Show Overdue", "Show Today", "Status", "Logout", "Exit"
+                ];
+                let mut prompt = "What would you like to do?".to_string();
+                if !self.config.auto_save {
+                    options.insert(options.len() - 2, "Save");
+                    if self.todo_manager.has_unsaved_changes() {
+                        prompt = format!("{} {}", prompt, "(unsaved changes)".yellow());
+                    }
+                }
+                
+                let selection = Select::new()
+                    .with_prompt(prompt)
+                    .items(&options)
+                    .interact()?;
+                
+                if let Err(e) = self.auth_manager.get_current_user() {
+                    println!("{} {}. Please login again.", self.icons.error.red(), e);
+                    break;
+                }
+                self.auth_manager.touch_session()?;
+                    
+                match options[selection] {
+                    "Add Todo" => self.add_todo(AddArgs::default()).await?,
+                    "List Todos" => self.list_todos(ListArgs::default()).await?,
+                    "Complete Todo" => self.complete_todo(None, None).await?,
+                    "Reopen Todo" => self.reopen_todo(None).await?,
+                    "Edit Todo" => self.edit_todo(None).await?,
+                    "Delete Todo" => self.delete_todo(None).await?,
+                    "Bulk Actions" => self.bulk_actions().await?,
+                    "Show Overdue" => self.show_overdue(ViewArgs::default()).await?,
+                    "Show Today" => self.show_today(ViewArgs::default()).await?,
+                    "Status" => self.show_status(false, false).await?,
+                    "Save" => self.save_changes().await?,
+                    "Logout" => {
+                        self.todo_manager.flush().await?;
+                        self.logout().await?;
+                        break;
+                    },
+                    _ => break,
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Writes what was held back with `auto_save` off.
+    async fn save_changes(&mut self) -> Result<()> {
+        if !self.todo_manager.has_unsaved_changes() {
+            self.note(format!("{} Nothing to save", self.icons.info.blue()));
+            return Ok(());
+        }
+        self.todo_manager.flush().await?;
+        self.note(format!("{} Changes saved", self.icons.success.green()));
+        Ok(())
+    }
+    
+    /// The first-run guide: what the tool does, creating an account (or
+    /// signing in), an optional sample todo, and the commands to try next.
+    /// Shown once per data directory; `--welcome` brings it back.
+    async fn onboarding(&mut self) -> Result<()> {
+        self.storage.mark_onboarded()?;
+        
+        println!();
+        println!("Todo CLI keeps a todo list per account. Every change is saved to {}", self.storage.data_dir().display());
+        println!("as JSON, with a readable copy in todos.md, and due dates drive the reminders");
+        println!("shown when you sign in.");
+        println!();
+        
+        if !self.auth_manager.is_authenticated() {
+            if self.auth_manager.has_users() {
+                if !Confirm::new().with_prompt("Sign in now?").default(true).interact()? {
+                    return Ok(());
+                }
+                self.login().await?;
+            } else {
+                if !Confirm::new().with_prompt("Create your account now?").default(true).interact()? {
+                    println!("{}", label(&self.icons.info, "Whenever you're ready: todo register").bright_cyan());
+                    return Ok(());
+                }
+                self.register_and_login().await?;
+            }
+        }
+        let Ok(current_user) = self.auth_manager.get_current_user() else {
+            return Ok(());
+        };
+        
+        if Confirm::new().with_prompt("Add a sample todo to try things out?").default(true).interact()? {
+            let due = (Local::now().date_naive() + chrono::Duration::days(1)).and_hms_opt(17, 0, 0);
+            let mut todo = Todo::new(
+                "Try out todo-cli".to_string(),
+                Some("Complete me with `todo complete`, or delete me with `todo delete`.".to_string()),
+                Priority::Low,
+                due,
+                current_user.id.clone(),
+            );
+            todo.tags = vec!["welcome".to_string()];
+            self.todo_manager.add_todo(todo.clone()).await?;
+            self.note(format!("{} Added a sample todo:", self.icons.success.green()));
+            self.print_todo(&todo);
+        }
+        
+        println!();
+        println!("{}", label(&self.icons.info, "A few commands to start with:").bright_cyan());
+        for (command, what) in [
+            ("todo add", "add a todo"),
+            ("todo list", "see your todos"),
+            ("todo complete", "tick one off"),
+            ("todo today", "what's due today"),
+            ("todo reminders", "everything that needs attention"),
+            ("todo --help", "every command"),
+        ] {
+            println!("  {:<16} {}", command, what.bright_black());
+        }
+        println!();
+        
+        Ok(())
+    }
+    
+    /// Registration with prompts, then signing in with the same credentials
+    /// so the guide can carry on.
+    async fn register_and_login(&mut self) -> Result<()> {
+        let username: String = Input::new()
+            .with_prompt("Username")
+            .interact_text()?;
+        let email: String = Input::new()
+            .with_prompt("Email")
+            .interact_text()?;
+        let password = Password::new()
+            .with_prompt("Password")
+            .with_confirmation("Confirm password", "Passwords don't match")
+            .interact()?;
+        
+        if let Err(e) = self.auth_manager.register(&username, &email, &password).await {
+            println!("{} Registration failed: {}", self.icons.error.red(), e);
+            return Ok(());
+        }
+        let (user, _) = self.auth_manager.login(&username, &password).await?;
+        self.apply_user_settings();
+        self.note(format!("{} Welcome, {}! {}", self.icons.success.green(), user.username.bright_green(), self.icons.wave));
+        Ok(())
+    }
+    
+    /// `<id> <priority> <status> <title> (<due>)`, with the marker columns
+    /// padded to the widest marker so custom icons still line up.
+    fn print_todo_oneline(&self, todo: &Todo) {
+        let icons = &self.icons;
+        let statuses = [&icons.status_pending, &icons.status_completed];
+        let status = match todo.status {
+            Status::Pending => statuses[0],
+            Status::Completed => statuses[1],
+        };
+        let priorities = [&icons.priority_low, &icons.priority_medium, &icons.priority_high];
+        let priority = match todo.priority {
+            Priority::Low => priorities[0],
+            Priority::Medium => priorities[1],
+            Priority::High => priorities[2],
+        };
+        let pad = |marker: &str, set: &[&String]| {
+            let width = set.iter().map(|m| console::measure_text_width(m)).max().unwrap_or(0);
+            format!("{}{}", marker, " ".repeat(width.saturating_sub(console::measure_text_width(marker))))
+        };
+        
+        let title = match todo.status {
+            Status::Pending => todo.title.bright_white().bold(),
+            Status::Completed => todo.title.bright_black(),
+        };
+        let due = match todo.due_date.map(|due| with_zone(due.format("%Y-%m-%d").to_string(), todo.due_tz.as_deref())) {
+            Some(due) if todo.is_overdue(Local::now()) => format!(" ({})", due).red().to_string(),
+            Some(due) => format!(" ({})", due).bright_blue().to_string(),
+            None => String::new(),
+        };
+        println!("{} {} {} {}{}", self.short_id(&todo.id).bright_black(), pad(priority, &priorities), pad(status, &statuses), title, due);
+    }
+    
+    fn priority_icon(&self, priority: &Priority) -> &str {
+        match priority {
+            Priority::Low => &self.icons.priority_low,
+            Priority::Medium => &self.icons.priority_medium,
+            Priority::High => &self.icons.priority_high,
+        }
+    }
+    
+    /// A stored due date as the user reads it: wall-clock time, followed by
+    /// the zone for zoned deadlines.
+    fn format_due(&self, due: chrono::NaiveDateTime, tz: Option<&str>) -> String {
+        with_zone(due.format(self.config.display_format()).to_string(), tz)
+    }
+    
+    fn print_todo(&self, todo: &Todo) {
+        let status_emoji = match todo.status {
+            Status::Pending => &self.icons.status_pending,
+            Status::Completed => &self.icons.status_completed,
+        };
+        
+        let priority_emoji = self.priority_icon(&todo.priority);
+        
+        let id_short = self.short_id(&todo.id);
+        
+        let hint = Some(self.config.urgency_hint_hours)
+            .filter(|&hours| hours > 0)
+            .and_then(|hours| todo::urgency_hint(todo, Local::now(), chrono::Duration::hours(hours)))
+            .map(|hint| label(&self.icons.urgent, hint).bright_yellow().to_string())
+            .unwrap_or_default();
+        
+        println!("{} {} {} [{}] {}{}", 
+            status_emoji, 
+            priority_emoji,
+            id_short.bright_black(),
+            todo.title.bright_white().bold(),
+            if todo.status == Status::Completed { self.icons.sparkle.as_str() } else { "" },
+            hint
+        );
+        
+        if let Some(description) = &todo.description {
+            let prefix = format!("   {} ", self.icons.description);
+            let indent = " ".repeat(console::measure_text_width(&prefix));
+            let width = terminal_width().saturating_sub(indent.len());
+            for (i, line) in wrap_text(description, width).iter().enumerate() {
+                let lead = if i == 0 { &prefix } else { &indent };
+                println!("{}{}", lead, line.bright_black());
+            }
+        }
+        
+        if let Some(ref project) = todo.project {
+            println!("   {} {}", self.icons.project, project.bright_cyan());
+        }
+        
+        if let Some(ref waiting) = todo.waiting {
+            println!("   {} {}", self.icons.waiting, waiting_label(waiting.on.as_deref()).bright_magenta());
+        }
+        
+        if todo.someday {
+            println!("   {} {}", self.icons.someday, "Someday/maybe".bright_magenta());
+        }
+        
+        if let Some(minutes) = todo.estimate_minutes {
+            println!("   {} Estimate: {}", self.icons.estimate, dates::format_minutes(minutes).bright_cyan());
+        }
+        
+        if let Some(recurrence) = todo.recurrence {
+            println!("   {} Repeats {}", self.icons.recurring, recurrence.label().bright_cyan());
+        }
+        
+        if !todo.reminder_offsets.is_empty() {
+            let offsets: Vec<String> = todo.reminder_offsets.iter().map(|&m| dates::format_offset(m)).collect();
+            println!("   {} Remind {} before", self.icons.reminder_custom, offsets.join(", ").bright_cyan());
+        }
+        
+        if !todo.tags.is_empty() {
+            let tags: Vec<String> = todo.tags.iter().map(|t| format!("#{}", t)).collect();
+            println!("   {} {}", self.icons.tags, tags.join(" ").bright_cyan());
+        }
+        
+        if todo.created_by != todo.user_id {
+            let creator = self.auth_manager.get_user_by_id(&todo.created_by)
+                .map(|u| u.username.as_str())
+                .unwrap_or("unknown user");
+            println!("   {} Created by {}", self.icons.creator, creator.bright_magenta());
+        }
+        
+        let viewer_id = self.subject().ok().map(|u| u.id);
+        if viewer_id.as_deref().is_some_and(|id| id != todo.user_id) {
+            let owner = self.auth_manager.get_user_by_id(&todo.user_id)
+                .map(|u| u.username.as_str())
+                .unwrap_or("unknown user");
+            println!("   {} Shared by {}", self.icons.shared, owner.bright_magenta());
+        } else if !todo.shared_with.is_empty() {
+            let names: Vec<&str> = todo.shared_with.iter()
+                .filter_map(|id| self.auth_manager.get_user_by_id(id))
+                .map(|u| u.username.as_str())
+                .collect();
+            println!("   {} Shared with {}", self.icons.shared, names.join(", ").bright_magenta());
+        }
+        
+        if let Some((done, total)) = todo.subtask_progress() {
+            println!("   {}", self.progress_bar(done, total));
+            for (i, subtask) in todo.subtasks.iter().enumerate() {
+                let checkbox = if subtask.done { "[x]".green() } else { "[ ]".bright_black() };
+                println!("      {}. {} {}", i + 1, checkbox, subtask.title);
+            }
+        }
+        
+        for (i, milestone) in todo.milestones.iter().enumerate() {
+            let checkbox = if milestone.done { "[x]".green() } else { "[ ]".bright_black() };
+            let date = milestone.date.format(self.config.display_format()).to_string();
+            let missed = !milestone.done && todo.status == Status::Pending && dates::is_past(milestone.date, None, Local::now());
+            println!("   {} {}. {} {} {}",
+                self.icons.milestone,
+                i + 1,
+                checkbox,
+                milestone.label,
+                if missed { date.red() } else { date.bright_blue() });
+        }
+        
+        if let Some(due_date) = todo.due_date {
+            let shown = self.format_due(due_date, todo.due_tz.as_deref());
+            
+            if todo.is_overdue(Local::now()) {
+                println!("   {} Due: {} {}", self.icons.overdue, shown.red(), "(OVERDUE)".red().bold());
+            } else {
+                println!("   {} Due: {}", self.icons.due, shown.bright_blue());
+            }
+        }
+        
+        // Undated todos show how long they've been sitting, colored by staleness
+        let age = match reminder::aging(todo, Local::now(), self.config.reminders.stale_days) {
+            Some((band, age)) => {
+                let text = match age.num_days() {
+                    0 => "  (new)".to_string(),
+                    days => format!("  ({} old)", pluralize(days, "day")),
+                };
+                match band {
+                    Aging::Fresh => text.green(),
+                    Aging::Ageing => text.yellow(),
+                    Aging::Stale => text.red(),
+                }.to_string()
+            },
+            None => String::new(),
+        };
+        println!("   {} Created: {}{}", self.icons.created, todo.created_at.format(self.config.display_format()).to_string().bright_black(), age);
+        
+        if let Some(done_at) = todo.completed_time() {
+            println!("   {} Completed: {}", self.icons.status_completed, done_at.with_timezone(&Local).format(self.config.display_format()).to_string().green());
+        }
+        
+        if let Some(ref note) = todo.completion_note {
+            println!("   {} Outcome: {}", self.icons.completion_note, note.green());
+        }
+    }
+    
+    /// Renders a fixed-width `[███░░] 60%` bar. Without color support the block
+    /// characters (or `#`/`-` in ASCII mode) still carry the information.
+    fn progress_bar(&self, done: usize, total: usize) -> String {
+        const WIDTH: usize = 10;
+        let filled = (done * WIDTH).checked_div(total).unwrap_or(0);
+        let percent = (done * 100).checked_div(total).unwrap_or(0);
+        format!("[{}{}] {}%",
+            self.icons.bar_full.repeat(filled).green(),
+            self.icons.bar_empty.repeat(WIDTH - filled).bright_black(),
+            percent
+        )
+    }
+}
+
+/// Renders the `prompt` template for the signed-in user, or `None` when nobody
+/// is. Reads the session and todos directly and never touches the session, so
+/// prompts don't count as activity for the idle timeout.
+fn prompt_line(storage: &Storage, format: &str) -> Result<Option<String>> {
+    let config = storage.load_config()?;
+    let auth_manager = AuthManager::new(storage, &config)?;
+    let user = match auth_manager.get_current_user() {
+        Ok(user) => user,
+        Err(_) => return Ok(None),
+    };
+    
+    let now = Local::now();
+    let today = now.date_naive();
+    let todos = storage.load_todos()?;
+    let visible: Vec<&Todo> = todos.values()
+        .filter(|t| t.is_visible_to(&user.id) && t.status == Status::Pending && !t.someday && !t.archived)
+        .collect();
+    let overdue = visible.iter().filter(|t| t.is_overdue(now)).count();
+    let due_today = visible.iter()
+        .filter(|t| t.due_day() == Some(today))
+        .count();
+    
+    Ok(Some(format
+        .replace("{pending}", &visible.len().to_string())
+        .replace("{overdue}", &overdue.to_string())
+        .replace("{today}", &due_today.to_string())))
+}
+
+/// The schema of what `command --json` prints. Generated from the same
+/// types that are serialized, so the two can't drift apart.
+fn output_schema(command: &str) -> schemars::schema::RootSchema {
+    match command {
+        "list" => schemars::schema_for!(Vec<Todo>),
+        "status" => schemars::schema_for!(StatusReport),
+        "stats" => schemars::schema_for!(StatsReport),
+        "doctor" => schemars::schema_for!(doctor::Report),
+        "reminders" => schemars::schema_for!(ReminderCounts),
+        _ => unreachable!("clap only accepts commands with a JSON mode"),
+    }
+}
+
+fn run_doctor(data_dir: Option<PathBuf>, ascii: bool, json: bool) -> Result<()> {
+    let mut storage = Storage::new(data_dir)?;
+    let checks = doctor::run_checks(&mut storage);
+    let failed = checks.iter().filter(|c| !c.ok).count();
+    let total = checks.len();
+    
+    if json {
+        let report = doctor::Report { ok: doctor::all_ok(&checks), checks };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let config = storage.load_config().unwrap_or_default();
+        let icons = Icons::new(ascii || config.ascii, &config.emojis);
+        for check in &checks {
+            let mark = if check.ok { icons.success.green() } else { icons.error.red() };
+            println!("{} {}: {}", mark, check.name.bold(), check.detail);
+        }
+    }
+    
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, total);
+    }
+    Ok(())
+}
+
+/// Columns available for output; 80 when stdout is not a terminal or the size is unknown.
+fn terminal_width() -> usize {
+    console::Term::stdout()
+        .size_checked()
+        .map(|(_, cols)| cols as usize)
+        .unwrap_or(80)
+}
+
+/// Greedy word wrap to `width` display columns. Words longer than a line are
+/// kept whole rather than split, and explicit newlines are preserved.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(20);
+    let mut lines = Vec::new();
+    
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let needed = console::measure_text_width(&line) + 1 + console::measure_text_width(word);
+            if !line.is_empty() && needed > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    
+    fn open_app(dir: &Path) -> TodoApp {
+        TodoApp::new(Storage::new(Some(dir.to_path_buf())).unwrap(), true, Verbosity::Quiet, false).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn read_commands_work_on_an_empty_data_dir() {
+        let dir = std::env::temp_dir().join(format!("todo-cli-test-{}", uuid::Uuid::new_v4()));
+        
+        // Nobody has registered: reads point at `register` instead of failing oddly
+        let mut app = open_app(&dir);
+        let error = app.ensure_authenticated().await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<AuthError>(), Some(AuthError::NoAccounts)));
+        app.show_status(false, false).await.unwrap();
+        app.show_status(true, true).await.unwrap();
+        app.verify(false).unwrap();
+        
+        // One account, no todos yet
+        let user = auth::User {
+            id: uuid::Uuid::new_v4().to_string(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            password_hash: String::new(),
+            created_at: chrono::Utc::now(),
+            last_login: None,
+            settings: UserSettings::default(),
+            is_admin: true,
+            failed_logins: auth::FailedLogins::default(),
+        };
+        app.storage.save_users(&HashMap::from([(user.id.clone(), user)])).unwrap();
+        let mut app = open_app(&dir);
+        app.auth_manager.auto_login().unwrap();
+        app.ensure_authenticated().await.unwrap();
+        
+        app.list_todos(ListArgs::default()).await.unwrap();
+        app.show_overdue(ViewArgs::default()).await.unwrap();
+        app.show_today(ViewArgs::default()).await.unwrap();
+        app.show_plan().await.unwrap();
+        app.search_todos("anything", None, false, ViewArgs::default()).await.unwrap();
+        app.show_completed("week").await.unwrap();
+        app.show_calendar(None, None).await.unwrap();
+        app.check_reminders(None).await.unwrap();
+        app.reminder_summary(false).await.unwrap();
+        app.reminder_summary(true).await.unwrap();
+        app.show_status(false, true).await.unwrap();
+        app.show_stats(false).await.unwrap();
+        app.show_stats(true).await.unwrap();
+        app.show_leaderboard().unwrap();
+        app.show_history(10).unwrap();
+        app.verify(false).unwrap();
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+
+/// Schema version every data file is written at. Bump it together with a new
+/// step at the end of each affected migration list.
+pub const CURRENT_VERSION: u64 = 1;
+
+/// One upgrade step, taking a file's JSON from version N to N + 1.
+pub type Migration = fn(Value) -> Result<Value>;
+
+/// Steps for `todos.json`; entry N upgrades from version N.
+pub const TODOS: &[Migration] = &[todos_v1];
+
+/// Steps for `users.json`; entry N upgrades from version N.
+pub const USERS: &[Migration] = &[users_v1];
+
+/// The version a file was written at. Files from before versioning are bare
+/// maps without the field, which makes them version 0.
+pub fn version_of(value: &Value) -> u64 {
+    value.get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+/// Runs whichever steps `value` still needs, in order. Returns the upgraded
+/// JSON and the version it started at.
+pub fn upgrade(mut value: Value, steps: &[Migration]) -> Result<(Value, u64)> {
+    let from = version_of(&value);
+    if from > CURRENT_VERSION {
+        bail!("Data file is schema version {}, newer than this build supports ({}). Please upgrade todo-cli", from, CURRENT_VERSION);
+    }
+
+    for step in &steps[from as usize..] {
+        value = step(value)?;
+    }
+    Ok((value, from))
+}
+
+/// Version 1 wraps the bare id → todo map in a versioned envelope.
+fn todos_v1(value: Value) -> Result<Value> {
+    Ok(json!({ "schema_version": 1, "todos": value }))
+}
+
+/// Version 1 wraps the bare id → user map in a versioned envelope.
+fn users_v1(value: Value) -> Result<Value> {
+    Ok(json!({ "schema_version": 1, "users": value }))
+}
@@ -1,114 +1,494 @@
-use chrono::{DateTime, Local, Duration};
-use crate::todo::{Todo, Status};
+use chrono::{DateTime, Local, Duration, Utc};
+use std::collections::HashMap;
+use crate::config::{Config, ReminderConfig, UrgencyWeights};
+use crate::dates;
+use crate::icons::{Icons, label};
+use crate::todo::{Acknowledgement, Priority, Todo, Status};
 
 #[derive(Debug)]
 pub struct Reminder {
     pub message: String,
     pub emoji: String,
+    #[allow(dead_code)]
     pub priority: ReminderPriority,
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum ReminderPriority {
     Info,
     Warning,
     Critical,
 }
 
-pub struct ReminderService;
+/// Why a reminder fired.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReminderKind {
+    Overdue,
+    DueWithinHour,
+    DueSoon,
+    DueTomorrow,
+    DueThisWeek,
+    /// Inside one of the todo's own `--remind-before` windows.
+    Custom { offset: Duration },
+    /// An undated todo that has been pending for `age`.
+    Stale { age: Duration },
+    /// A todo that has been waiting on someone for `age`.
+    WaitingStale { age: Duration },
+    /// The todo's next milestone is coming up within the upcoming window.
+    Milestone { label: String },
+    /// The todo's next milestone date has passed without it being done.
+    MilestoneMissed { label: String },
+    /// This many todos have timestamps in the future.
+    ClockSkew { todos: usize },
+}
+
+impl ReminderKind {
+    /// The `reminder_templates` key for this kind.
+    pub fn template_key(&self) -> &'static str {
+        match self {
+            ReminderKind::Overdue => "overdue",
+            ReminderKind::DueWithinHour => "due_within_hour",
+            ReminderKind::DueSoon => "due_soon",
+            ReminderKind::DueTomorrow => "due_tomorrow",
+            ReminderKind::DueThisWeek => "due_this_week",
+            ReminderKind::Custom { .. } => "custom",
+            ReminderKind::Stale { .. } => "stale",
+            ReminderKind::WaitingStale { .. } => "waiting",
+            ReminderKind::Milestone { .. } => "milestone",
+            ReminderKind::MilestoneMissed { .. } => "milestone_missed",
+            ReminderKind::ClockSkew { .. } => "clock_skew",
+        }
+    }    
+    /// How pressing this kind is, for deciding whether an acknowledged
+    /// reminder has got worse. 0 is reserved for "no reminder".
+    pub fn urgency(&self) -> u8 {
+        match self {
+            ReminderKind::Stale { .. }
+            | ReminderKind::WaitingStale { .. }
+            | ReminderKind::ClockSkew { .. } => 1,
+            ReminderKind::DueThisWeek | ReminderKind::Milestone { .. } => 2,
+            ReminderKind::DueTomorrow => 3,
+            ReminderKind::DueSoon
+            | ReminderKind::Custom { .. }
+            | ReminderKind::MilestoneMissed { .. } => 4,
+            ReminderKind::DueWithinHour => 5,
+            ReminderKind::Overdue => 6,
+        }
+    }
+}
+
+/// A reminder as data, with no wording or symbols attached.
+#[derive(Debug, Clone)]
+pub struct ReminderEvent {
+    /// The todo concerned; `None` for warnings about the data as a whole.
+    pub todo_id: Option<String>,
+    pub kind: ReminderKind,
+    /// Time left until the due date, negative once overdue. `None` for
+    /// reminders not tied to a due date.
+    pub due_in: Option<Duration>,
+    pub priority: ReminderPriority,
+}
+
+impl ReminderEvent {
+    fn for_todo(todo: &Todo, kind: ReminderKind, due_in: Option<Duration>, priority: ReminderPriority) -> Self {
+        Self {
+            todo_id: Some(todo.id.clone()),
+            kind,
+            due_in,
+            priority,
+        }
+    }
+}
+
+pub struct ReminderService {
+    /// User overrides for reminder wording, keyed like `default_template`.
+    templates: HashMap<String, String>,
+    thresholds: ReminderConfig,
+    icons: Icons,
+}
+
+/// How far into the future a timestamp may be before we treat it as clock skew
+/// rather than ordinary drift between machines.
+const CLOCK_SKEW_TOLERANCE_MINUTES: i64 = 5;
+
+/// Time elapsed from `then` to `now`, clamped to zero so a future-dated
+/// timestamp (wrong clock, edited data) never produces negative ages.
+pub fn elapsed_since(now: DateTime<Local>, then: DateTime<Utc>) -> Duration {
+    now.signed_duration_since(then).max(Duration::zero())
+}
+
+/// How long an undated pending todo has been sitting, measured against
+/// `stale_days`, the age at which it starts getting stale reminders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aging {
+    /// Younger than half of `stale_days`.
+    Fresh,
+    /// From half of `stale_days` up to it.
+    Ageing,
+    /// Older than `stale_days`, so it is being reminded about too.
+    Stale,
+}
+
+/// The todo's age and its band. `None` for completed todos and for ones with
+/// a due date, which says more than the age does.
+pub fn aging(todo: &Todo, now: DateTime<Local>, stale_days: i64) -> Option<(Aging, Duration)> {
+    if todo.status != Status::Pending || todo.due_date.is_some() {
+        return None;
+    }
+    let age = elapsed_since(now, todo.created_at);
+    let band = if age > Duration::days(stale_days) {
+        Aging::Stale
+    } else if age >= Duration::days(stale_days / 2) {
+        Aging::Ageing
+    } else {
+        Aging::Fresh
+    };
+    Some((band, age))
+}
+
+/// Whether `kind` was dismissed with `todo ack`: the acknowledgement must be
+/// newer than the todo's last edit and the reminder no more urgent than it
+/// was at the time.
+fn is_acknowledged(todo: &Todo, kind: &ReminderKind) -> bool {
+    todo.acknowledged.as_ref()
+        .is_some_and(|ack| ack.at >= todo.updated_at && kind.urgency() <= ack.urgency)
+}
+
+/// One number for how pressing a todo is, used to order reminders:
+///
+/// `weights.priority * p + weights.due * t`
+///
+/// where `p` is 0 for Low, 0.5 for Medium and 1 for High, and `t` depends on
+/// the due date: `1 / (1 + days left)` before it,
+/// `1 + weights.overdue_per_day * days late` after it, and 0 without one.
+/// Days are fractional, so the score changes smoothly instead of in bands.
+pub fn urgency_score(todo: &Todo, now: DateTime<Local>, weights: &UrgencyWeights) -> f64 {
+    let p = match todo.priority {
+        Priority::Low => 0.0,
+        Priority::Medium => 0.5,
+        Priority::High => 1.0,
+    };
+    let t = match todo.due_at() {
+        Some(due) => {
+            let days = (due - now).num_minutes() as f64 / (24.0 * 60.0);
+            if days < 0.0 {
+                1.0 + weights.overdue_per_day * -days
+            } else {
+                1.0 / (1.0 + days)
+            }
+        },
+        None => 0.0,
+    };
+    weights.priority * p + weights.due * t
+}
+
+/// Formats a count with its unit, e.g. "1 day" or "3 days".
+pub fn pluralize(count: i64, unit: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+/// Built-in wording for each reminder kind. `{title}` is the todo title and
+/// `{time}` an already pluralized amount such as "2 days". Custom reminders
+/// also get `{offset}`, the window the user asked for, such as "4h", and
+/// milestone reminders `{milestone}`, the milestone's label.
+fn default_template(kind: &str) -> &'static str {
+    match kind {
+        "overdue" => "'{title}' is {time} overdue!",
+        "due_within_hour" => "'{title}' is due in less than an hour!",
+        "due_soon" => "'{title}' is due in {time}!",
+        "due_tomorrow" => "'{title}' is due tomorrow!",
+        "due_this_week" => "'{title}' is due in {time}!",
+        "custom" => "'{title}' is due in {time} (your {offset} reminder)",
+        "stale" => "'{title}' has been pending for {time} - consider setting a due date!",
+        "waiting" => "'{title}' has been waiting for {time} - time to follow up?",
+        "milestone" => "'{title}': milestone '{milestone}' is due in {time}",
+        "milestone_missed" => "'{title}': milestone '{milestone}' is {time} overdue!",
+        "clock_skew" => "Found {time} with timestamps in the future - check your system clock!",
+        _ => "{title}",
+    }
+}
 
 impl ReminderService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(config: &Config, icons: Icons) -> Self {
+        Self {
+            templates: config.reminder_templates.clone(),
+            thresholds: config.reminders.clone(),
+            icons,
+        }
     }
     
-    pub fn get_reminders(&self, todos: &[Todo]) -> Vec<Reminder> {
-        let mut reminders = Vec::new();
+    fn render(&self, kind: &ReminderKind, title: &str, time: &str) -> String {
+        let key = kind.template_key();
+        let template = self.templates.get(key)
+            .map(String::as_str)
+            .unwrap_or_else(|| default_template(key));
+        let offset = match kind {
+            ReminderKind::Custom { offset } => dates::format_offset(offset.num_minutes()),
+            _ => String::new(),
+        };
+        let milestone = match kind {
+            ReminderKind::Milestone { label } | ReminderKind::MilestoneMissed { label } => label.as_str(),
+            _ => "",
+        };
+        template.replace("{title}", title)
+            .replace("{time}", time)
+            .replace("{offset}", &offset)
+            .replace("{milestone}", milestone)
+    }
+    
+    /// The event for the tightest of the todo's own windows it is inside.
+    /// Within an hour of the due date it is critical, otherwise a warning.
+    fn custom_event(&self, todo: &Todo, time_left: Duration) -> Option<ReminderEvent> {
+        let offset = todo.reminder_offsets.iter()
+            .map(|&minutes| Duration::minutes(minutes))
+            .filter(|&offset| time_left <= offset)
+            .min()?;
+        
+        let priority = if time_left < Duration::hours(1) {
+            ReminderPriority::Critical
+        } else {
+            ReminderPriority::Warning
+        };
+        Some(ReminderEvent::for_todo(todo, ReminderKind::Custom { offset }, Some(time_left), priority))
+    }
+    
+    /// The event for the tightest scheduled window `todo` is inside, if any.
+    /// Windows of 3 hours or less are critical and of a day or less a warning.
+    fn scheduled_event(&self, todo: &Todo, time_left: Duration) -> Option<ReminderEvent> {
+        let window = self.thresholds.schedule.for_priority(&todo.priority).iter()
+            .copied()
+            .filter(|&hours| time_left <= Duration::hours(hours))
+            .min()?;
+        
+        let priority = if window <= 3 {
+            ReminderPriority::Critical
+        } else if window <= 24 {
+            ReminderPriority::Warning
+        } else {
+            ReminderPriority::Info
+        };
+        
+        let kind = if time_left < Duration::hours(1) {
+            ReminderKind::DueWithinHour
+        } else if time_left < Duration::days(1) {
+            ReminderKind::DueSoon
+        } else {
+            ReminderKind::DueThisWeek
+        };
+        
+        Some(ReminderEvent::for_todo(todo, kind, Some(time_left), priority))
+    }
+    
+    /// The event for the todo's next open milestone, if it is within the
+    /// upcoming window or already past.
+    fn milestone_event(&self, todo: &Todo, now: DateTime<Local>) -> Option<ReminderEvent> {
+        let milestone = todo.next_milestone()?;
+        let time_left = dates::due_instant(milestone.date, None) - now;
+        let label = milestone.label.clone();
+        
+        if time_left < Duration::zero() {
+            Some(ReminderEvent::for_todo(todo, ReminderKind::MilestoneMissed { label }, Some(time_left), ReminderPriority::Warning))
+        } else if time_left < Duration::days(self.thresholds.upcoming_days) {
+            Some(ReminderEvent::for_todo(todo, ReminderKind::Milestone { label }, Some(time_left), ReminderPriority::Info))
+        } else {
+            None
+        }
+    }
+    
+    /// Everything worth reminding about, as data: warnings about the data as
+    /// a whole first, then todos by `urgency_score`, highest first. Front
+    /// ends that render their own text should use this; `get_reminders`
+    /// formats the same events for the terminal. Acknowledged reminders are
+    /// left out until the todo changes or gets more urgent.
+    pub fn get_reminder_events(&self, todos: &[Todo]) -> Vec<ReminderEvent> {
+        let mut scored = Vec::new();
         let now = Local::now();
         
-        for todo in todos.iter().filter(|t| t.status == Status::Pending) {
-            if let Some(due_date) = todo.due_date {
-                let due_datetime = DateTime::<Local>::from_naive_utc_and_offset(due_date, *now.offset());
-                let time_diff = due_datetime - now;
-                
-                // Overdue tasks
-                if time_diff < Duration::zero() {
-                    let days_overdue = (-time_diff).num_days();
-                    let hours_overdue = (-time_diff).num_hours();
-                    
-                    let message = if days_overdue > 0 {
-                        format!("'{}' is {} day(s) overdue!", todo.title, days_overdue)
-                    } else {
-                        format!("'{}' is {} hour(s) overdue!", todo.title, hours_overdue)
-                    };
-                    
-                    reminders.push(Reminder {
-                        message,
-                        emoji: "🚨".to_string(),
-                        priority: ReminderPriority::Critical,
-                    });
-                }
-                // Due today
-                else if time_diff < Duration::days(1) {
-                    let hours_left = time_diff.num_hours();
-                    let message = if hours_left < 1 {
-                        format!("'{}' is due in less than an hour!", todo.title)
-                    } else {
-                        format!("'{}' is due in {} hour(s)!", todo.title, hours_left)
-                    };
-                    
-                    reminders.push(Reminder {
-                        message,
-                        emoji: "⏰".to_string(),
-                        priority: ReminderPriority::Warning,
-                    });
-                }
-                // Due tomorrow
-                else if time_diff < Duration::days(2) {
-                    reminders.push(Reminder {
-                        message: format!("'{}' is due tomorrow!", todo.title),
-                        emoji: "📅".to_string(),
-                        priority: ReminderPriority::Info,
-                    });
-                }
-                // Due within a week
-                else if time_diff < Duration::days(7) {
-                    let days_left = time_diff.num_days();
-                    reminders.push(Reminder {
-                        message: format!("'{}' is due in {} day(s)!", todo.title, days_left),
-                        emoji: "📋".to_string(),
-                        priority: ReminderPriority::Info,
-                    });
+        for todo in todos {
+            if let Some(event) = self.todo_event(todo, now) {
+                if !is_acknowledged(todo, &event.kind) {
+                    scored.push((urgency_score(todo, now, &self.thresholds.urgency), event));
                 }
             }
         }
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut events: Vec<ReminderEvent> = scored.into_iter().map(|(_, event)| event).collect();
+        
+        // Flag future-dated todos once rather than per todo
+        let skew_limit = now + Duration::minutes(CLOCK_SKEW_TOLERANCE_MINUTES);
+        let future_dated = todos.iter()
+            .filter(|t| t.created_at > skew_limit || t.updated_at > skew_limit)
+            .count();
+        if future_dated > 0 {
+            events.insert(0, ReminderEvent {
+                todo_id: None,
+                kind: ReminderKind::ClockSkew { todos: future_dated },
+                due_in: None,
+                priority: ReminderPriority::Warning,
+            });
+        }
+        
+        events
+    }
+    
+    /// The reminder `todo` warrants right now, acknowledged or not.
+    fn todo_event(&self, todo: &Todo, now: DateTime<Local>) -> Option<ReminderEvent> {
+        if todo.status != Status::Pending || todo.someday || todo.is_snoozed(now) {
+            return None;
+        }
+        
+        // Delegated todos only nag once they've waited too long
+        if let Some(waiting) = &todo.waiting {
+            let age = elapsed_since(now, waiting.since);
+            return (age > Duration::days(self.thresholds.stale_days))
+                .then(|| ReminderEvent::for_todo(todo, ReminderKind::WaitingStale { age }, None, ReminderPriority::Info));
+        }
         
-        // Check for todos without due dates that are old
-        for todo in todos.iter().filter(|t| t.status == Status::Pending && t.due_date.is_none()) {
-            let age = now.signed_duration_since(todo.created_at);
-            
-            if age > Duration::days(7) {
-                let days_old = age.num_days();
-                reminders.push(Reminder {
-                    message: format!("'{}' has been pending for {} day(s) - consider setting a due date!", todo.title, days_old),
-                    emoji: "💭".to_string(),
-                    priority: ReminderPriority::Info,
-                });
+        // A milestone speaks up only when it is more pressing than the deadline
+        let milestone = self.milestone_event(todo, now);
+        let due = self.due_event(todo, now);
+        match (due, milestone) {
+            (Some(due), Some(milestone)) if milestone.kind.urgency() > due.kind.urgency() => Some(milestone),
+            (Some(due), _) => Some(due),
+            (None, milestone) => milestone,
+        }
+    }
+    
+    /// The reminder for the todo's own due date, or for it having none.
+    fn due_event(&self, todo: &Todo, now: DateTime<Local>) -> Option<ReminderEvent> {
+        let Some(due_datetime) = todo.due_at() else {
+            // Undated todos that have been pending a long time
+            let age = elapsed_since(now, todo.created_at);
+            return (age > Duration::days(self.thresholds.stale_days))
+                .then(|| ReminderEvent::for_todo(todo, ReminderKind::Stale { age }, None, ReminderPriority::Info));
+        };
+        
+        let time_diff = due_datetime - now;
+        
+        if time_diff < Duration::zero() {
+            Some(ReminderEvent::for_todo(todo, ReminderKind::Overdue, Some(time_diff), ReminderPriority::Critical))
+        }
+        // The todo's own reminders take over from every global threshold
+        else if !todo.reminder_offsets.is_empty() {
+            self.custom_event(todo, time_diff)
+        }
+        // Priority-specific warning points take over from the generic checks
+        else if !self.thresholds.schedule.for_priority(&todo.priority).is_empty() {
+            self.scheduled_event(todo, time_diff)
+        }
+        // Due today
+        else if time_diff < Duration::days(1) {
+            let kind = if time_diff.num_hours() < 1 {
+                ReminderKind::DueWithinHour
+            } else {
+                ReminderKind::DueSoon
+            };
+            Some(ReminderEvent::for_todo(todo, kind, Some(time_diff), ReminderPriority::Warning))
+        }
+        // Due tomorrow
+        else if time_diff < Duration::days(2) {
+            Some(ReminderEvent::for_todo(todo, ReminderKind::DueTomorrow, Some(time_diff), ReminderPriority::Info))
+        }
+        // Due within the upcoming window
+        else if time_diff < Duration::days(self.thresholds.upcoming_days) {
+            Some(ReminderEvent::for_todo(todo, ReminderKind::DueThisWeek, Some(time_diff), ReminderPriority::Info))
+        } else {
+            None
+        }
+    }
+    
+    /// An acknowledgement of `todo`'s current reminder, or `None` if it has
+    /// nothing to remind about or that reminder is already acknowledged.
+    pub fn acknowledge(&self, todo: &Todo) -> Option<Acknowledgement> {
+        self.todo_event(todo, Local::now())
+            .filter(|event| !is_acknowledged(todo, &event.kind))
+            .map(|event| Acknowledgement {
+                at: Utc::now(),
+                urgency: event.kind.urgency(),
+            })
+    }
+    
+    /// `(critical, warning, info)` counts of the reminders `get_reminders`
+    /// would list, for status bars and prompts.
+    pub fn count_by_priority(&self, todos: &[Todo]) -> (usize, usize, usize) {
+        let mut counts = (0, 0, 0);
+        for event in self.get_reminder_events(todos) {
+            match event.priority {
+                ReminderPriority::Critical => counts.0 += 1,
+                ReminderPriority::Warning => counts.1 += 1,
+                ReminderPriority::Info => counts.2 += 1,
             }
         }
+        counts
+    }
+    
+    pub fn get_reminders(&self, todos: &[Todo]) -> Vec<Reminder> {
+        self.get_reminder_events(todos).into_iter()
+            .map(|event| self.present(event, todos))
+            .collect()
+    }
+    
+    /// Turns an event into terminal text using the configured templates.
+    fn present(&self, event: ReminderEvent, todos: &[Todo]) -> Reminder {
+        let title = event.todo_id.as_ref()
+            .and_then(|id| todos.iter().find(|t| &t.id == id))
+            .map(|t| t.title.as_str())
+            .unwrap_or("");
+        let due_in = event.due_in.unwrap_or_else(Duration::zero);
         
-        // Sort reminders by priority (Critical first, then Warning, then Info)
-        reminders.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
+        let (time, emoji) = match event.kind {
+            ReminderKind::Overdue => {
+                let late = -due_in;
+                let time = if late.num_days() > 0 {
+                    pluralize(late.num_days(), "day")
+                } else {
+                    pluralize(late.num_hours(), "hour")
+                };
+                (time, &self.icons.reminder_overdue)
+            },
+            ReminderKind::DueWithinHour => (String::new(), &self.icons.reminder_due_soon),
+            ReminderKind::DueSoon => (pluralize(due_in.num_hours(), "hour"), &self.icons.reminder_due_soon),
+            ReminderKind::DueTomorrow => (String::new(), &self.icons.reminder_due_tomorrow),
+            ReminderKind::DueThisWeek => (pluralize(due_in.num_days(), "day"), &self.icons.reminder_due_this_week),
+            ReminderKind::Custom { .. } => {
+                let time = if due_in.num_hours() > 0 {
+                    pluralize(due_in.num_hours(), "hour")
+                } else {
+                    pluralize(due_in.num_minutes(), "minute")
+                };
+                (time, &self.icons.reminder_custom)
+            },
+            ReminderKind::Stale { age } => (pluralize(age.num_days(), "day"), &self.icons.reminder_stale),
+            ReminderKind::WaitingStale { age } => (pluralize(age.num_days(), "day"), &self.icons.reminder_waiting),
+            ReminderKind::Milestone { .. } | ReminderKind::MilestoneMissed { .. } => {
+                let span = due_in.abs();
+                let time = if span.num_days() > 0 {
+                    pluralize(span.num_days(), "day")
+                } else {
+                    pluralize(span.num_hours(), "hour")
+                };
+                (time, &self.icons.reminder_milestone)
+            },
+            ReminderKind::ClockSkew { todos } => (pluralize(todos as i64, "todo"), &self.icons.reminder_clock_skew),
+        };
         
-        reminders
+        Reminder {
+            message: self.render(&event.kind, title, &time),
+            emoji: emoji.clone(),
+            priority: event.priority,
+        }
     }
     
+    #[allow(dead_code)]
     pub fn get_daily_summary(&self, todos: &[Todo]) -> String {
         let pending_count = todos.iter().filter(|t| t.status == Status::Pending).count();
         let completed_today = todos.iter()
             .filter(|t| {
-                t.status == Status::Completed && 
-                t.updated_at.date_naive() == Local::now().date_naive()
+                t.completed_time().is_some_and(|at| at.with_timezone(&Local).date_naive() == Local::now().date_naive())
             })
             .count();
         
@@ -116,26 +496,124 @@ impl ReminderService {
         let due_today = todos.iter()
             .filter(|t| {
                 t.status == Status::Pending &&
-                t.due_date.map_or(false, |due| {
-                    let due_datetime = DateTime::<Local>::from_naive_utc_and_offset(due, *now.offset());
-                    due_datetime.date_naive() == now.date_naive()
-                })
+                t.due_at().is_some_and(|due| due.date_naive() == now.date_naive())
             })
             .count();
         
         let overdue = todos.iter()
             .filter(|t| {
                 t.status == Status::Pending &&
-                t.due_date.map_or(false, |due| {
-                    let due_datetime = DateTime::<Local>::from_naive_utc_and_offset(due, *now.offset());
-                    due_datetime < now
-                })
+                t.due_at().is_some_and(|due| due < now)
             })
             .count();
         
-        format!(
-            "📊 Daily Summary: {} pending, {} completed today, {} due today, {} overdue",
+        let summary = format!(
+            "Daily Summary: {} pending, {} completed today, {} due today, {} overdue",
             pending_count, completed_today, due_today, overdue
-        )
+        );
+        label(&self.icons.stats, &summary)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn service() -> ReminderService {
+        ReminderService::new(&Config::default(), Icons::new(true, &HashMap::new()))
+    }
+    
+    fn todo(title: &str) -> Todo {
+        Todo::new(title.to_string(), None, Priority::Medium, None, "alice".to_string())
+    }
+    
+    #[test]
+    fn future_dated_todos_never_get_negative_ages() {
+        let now = Local::now();
+        let mut future = todo("from the future");
+        future.created_at = Utc::now() + Duration::days(3);
+        future.updated_at = future.created_at;
+        
+        assert_eq!(elapsed_since(now, future.created_at), Duration::zero());
+        let (band, age) = aging(&future, now, 14).unwrap();
+        assert_eq!((band, age), (Aging::Fresh, Duration::zero()));
+        
+        let events = service().get_reminder_events(&[future]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, ReminderKind::ClockSkew { todos: 1 });
+        assert!(events.iter().all(|e| e.due_in.is_none_or(|d| d >= Duration::zero())));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn small_drift_is_not_reported_as_clock_skew() {
+        let mut todo = todo("slightly ahead");
+        todo.created_at = Utc::now() + Duration::minutes(1);
+        assert!(service().get_reminder_events(&[todo]).is_empty());
+    }
+    
+    #[test]
+    fn pluralize_uses_the_singular_only_for_one() {
+        assert_eq!(pluralize(0, "day"), "0 days");
+        assert_eq!(pluralize(1, "day"), "1 day");
+        assert_eq!(pluralize(2, "day"), "2 days");
+        assert_eq!(pluralize(1, "hour"), "1 hour");
+    }
+    
+    #[test]
+    fn reminder_messages_read_naturally_and_follow_templates() {
+        let mut overdue = todo("Taxes");
+        overdue.due_date = Some((Local::now() - Duration::hours(25)).naive_local());
+        let reminders = service().get_reminders(std::slice::from_ref(&overdue));
+        assert_eq!(reminders[0].message, "'Taxes' is 1 day overdue!");
+        
+        let mut config = Config::default();
+        config.reminder_templates.insert("overdue".to_string(), "{title}: {time} en retard".to_string());
+        let service = ReminderService::new(&config, Icons::new(true, &HashMap::new()));
+        assert_eq!(service.get_reminders(&[overdue])[0].message, "Taxes: 1 day en retard");
+    }
+    
+    #[test]
+    fn urgency_blends_due_dates_and_priority_into_one_order() {
+        let now = Local::now();
+        let weights = UrgencyWeights::default();
+        let scored = |title: &str, priority: Priority, due_hours: Option<i64>| {
+            let mut todo = Todo::new(title.to_string(), None, priority, None, "alice".to_string());
+            todo.due_date = due_hours.map(|hours| (now + Duration::hours(hours)).naive_local());
+            todo
+        };
+        let todos = [
+            scored("high, no due date", Priority::High, None),
+            scored("low, due in a week", Priority::Low, Some(24 * 7)),
+            scored("low, two days late", Priority::Low, Some(-48)),
+            scored("medium, due in an hour", Priority::Medium, Some(1)),
+            scored("high, due in three days", Priority::High, Some(72)),
+            scored("low, one day late", Priority::Low, Some(-24)),
+        ];
+        let mut ranked: Vec<&Todo> = todos.iter().collect();
+        ranked.sort_by(|a, b| urgency_score(b, now, &weights).total_cmp(&urgency_score(a, now, &weights)));
+        
+        let titles: Vec<&str> = ranked.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, [
+            "low, two days late",
+            "low, one day late",
+            "medium, due in an hour",
+            "high, due in three days",
+            "high, no due date",
+            "low, due in a week",
+        ]);
+    }
+    
+    #[test]
+    fn urgency_weights_change_the_order() {
+        let now = Local::now();
+        let mut urgent = todo("high, no due date");
+        urgent.priority = Priority::High;
+        let mut soon = todo("low, due tonight");
+        soon.priority = Priority::Low;
+        soon.due_date = Some((now + Duration::hours(12)).naive_local());
+        
+        let weights = UrgencyWeights::default();
+        assert!(urgency_score(&urgent, now, &weights) < urgency_score(&soon, now, &weights));
+        let priority_heavy = UrgencyWeights { priority: 5.0, ..weights };
+        assert!(urgency_score(&urgent, now, &priority_heavy) > urgency_score(&soon, now, &priority_heavy));
+    }
+}
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::reminder::elapsed_since;
+use crate::todo::Todo;
+
+/// Productivity numbers for one user's todos, serializable for `stats --json`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StatsReport {
+    /// Pending todos still in play; someday and archived ones aren't counted.
+    pub pending: usize,
+    pub completed: usize,
+    pub overdue: usize,
+    /// Pending todos set aside as someday/maybe.
+    pub someday: usize,
+    /// Completed share of all todos, from 0.0 to 1.0.
+    pub completion_rate: f64,
+    /// Consecutive days, ending today or yesterday, with at least one completion.
+    pub streak_days: u32,
+    /// Mean hours from creation to completion, if anything has been completed.
+    pub avg_completion_hours: Option<f64>,
+}
+
+/// What `status --json` prints. Only `authenticated` is present when nobody
+/// is logged in.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StatusReport {
+    pub authenticated: bool,
+    #[serde(flatten)]
+    pub account: Option<AccountStatus>,
+    /// Only with `--diagnostics`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<Diagnostics>,
+}
+
+/// Where the data lives and how much of it there is, for `status --diagnostics`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Diagnostics {
+    pub version: String,
+    pub data_dir: String,
+    pub config_file: String,
+    pub files: Vec<DataFile>,
+    /// Todos of every user, archived ones included.
+    pub total_todos: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DataFile {
+    pub path: String,
+    /// Absent for a file that hasn't been written yet.
+    pub bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AccountStatus {
+    pub username: String,
+    pub email: String,
+    /// Pending todos still in play; someday and archived ones aren't counted.
+    pub pending: usize,
+    pub completed: usize,
+    pub overdue: usize,
+    pub someday: usize,
+    pub total: usize,
+    pub pending_by_priority: PriorityCounts,
+}
+
+/// What `reminders --summary --json` prints.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ReminderCounts {
+    pub critical: usize,
+    pub warning: usize,
+    pub info: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PriorityCounts {
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+}
+
+impl StatsReport {
+    /// Builds every counter in one pass over `todos`, so large lists are
+    /// only walked once.
+    pub fn compute(todos: &[Todo], now: DateTime<Local>) -> Self {
+        let mut pending = 0;
+        let mut completed = 0;
+        let mut overdue = 0;
+        let mut someday = 0;
+        let mut total_minutes: i64 = 0;
+        let mut completion_days: HashSet<NaiveDate> = HashSet::new();
+
+        for todo in todos {
+            match todo.completed_time() {
+                None if todo.someday => someday += 1,
+                None if todo.archived => {},
+                None => {
+                    pending += 1;
+                    if todo.is_overdue(now) {
+                        overdue += 1;
+                    }
+                },
+                Some(done_at) => {
+                    completed += 1;
+                    let done_at = done_at.with_timezone(&Local);
+                    total_minutes += elapsed_since(done_at, todo.created_at).num_minutes();
+                    completion_days.insert(done_at.date_naive());
+                },
+            }
+        }
+
+        let completion_rate = if todos.is_empty() {
+            0.0
+        } else {
+            completed as f64 / todos.len() as f64
+        };
+        let avg_completion_hours = if completed == 0 {
+            None
+        } else {
+            Some(total_minutes as f64 / 60.0 / completed as f64)
+        };
+
+        Self {
+            pending,
+            completed,
+            overdue,
+            someday,
+            completion_rate,
+            streak_days: streak_days(&completion_days, now.date_naive()),
+            avg_completion_hours,
+        }
+    }
+}
+
+/// Counts back from today; a streak is still alive if the last completion was yesterday.
+fn streak_days(days: &HashSet<NaiveDate>, today: NaiveDate) -> u32 {
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        today - Duration::days(1)
+    };
+
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+    streak
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::todo::{Priority, Status};
+
+    #[test]
+    fn completions_recorded_before_creation_count_as_instant() {
+        let mut todo = Todo::new("skewed".to_string(), None, Priority::Low, None, "alice".to_string());
+        todo.created_at = Utc::now() + Duration::days(2);
+        todo.status = Status::Completed;
+        todo.completed_at = Some(Utc::now());
+
+        let report = StatsReport::compute(&[todo], Local::now());
+        assert_eq!(report.avg_completion_hours, Some(0.0));
+    }
+
+    #[test]
+    fn someday_todos_are_neither_pending_nor_overdue() {
+        let yesterday = (Local::now() - Duration::days(1)).naive_local();
+        let mut parked = Todo::new("parked".to_string(), None, Priority::Low, Some(yesterday), "alice".to_string());
+        parked.someday = true;
+        let late = Todo::new("late".to_string(), None, Priority::Low, Some(yesterday), "alice".to_string());
+
+        let report = StatsReport::compute(&[parked, late], Local::now());
+        assert_eq!((report.pending, report.overdue, report.someday), (1, 1, 1));
+    }
+}
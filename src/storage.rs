@@ -1,103 +1,589 @@
 #[allow(unused_imports)]
 use anyhow::{Context, Result};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use crate::auth::{User, Session};
-use crate::todo::{Todo, Status, Priority};
+use crate::config::Config;
+use crate::dates;
+use crate::migrations::{self, Migration};
+use crate::todo::{self, Todo, Status, Priority, SortKey, sort_todos};
+
+/// Differences between `todos.md` and the JSON data, as whole lines.
+#[derive(Debug, Default)]
+pub struct MarkdownDrift {
+    /// Lines the data calls for that the file lacks.
+    pub missing: Vec<String>,
+    /// Lines in the file that the data doesn't account for.
+    pub unexpected: Vec<String>,
+}
+
+impl MarkdownDrift {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// `todos.json` → `todos.json.gz`.
+fn gz_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.gz", path.display()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `None` for odd lengths or non-hex characters.
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len()).step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect()
+}
+
+/// Non-blank markdown lines, minus the timestamped header line.
+fn comparable_lines(markdown: &str) -> impl Iterator<Item = &str> {
+    markdown.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with("Generated by Todo CLI"))
+}
+
+/// Backslash-escapes whatever markdown would otherwise read as syntax, so
+/// user text like `- [x] trick` or `**bold**` comes back out the way it went
+/// in. Line-leading list markers are escaped too, since a description's
+/// continuation lines start a line of their own.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            escaped.push('\n');
+        }
+        let body = line.trim_start();
+        escaped.push_str(&line[..line.len() - body.len()]);
+        let marker = list_marker_at(body);
+        for (at, c) in body.char_indices() {
+            if Some(at) == marker || matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Byte offset of the character that would make `line` a list item: the
+/// `-` or `+` of a bullet, or the `.` / `)` of `1.` and `12)`.
+fn list_marker_at(line: &str) -> Option<usize> {
+    if line.starts_with(['-', '+']) {
+        return Some(0);
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    (digits > 0 && line[digits..].starts_with(['.', ')'])).then_some(digits)
+}
+
+/// For text that has to stay on one markdown line (titles, subtasks): escaped,
+/// with any newlines kept as `<br>`.
+fn escape_inline(text: &str) -> String {
+    escape_markdown(text.trim()).replace('\n', "<br>")
+}
+
+/// Where `Storage` keeps its bytes. Paths look the same either way; only
+/// `DiskBackend` actually touches the filesystem.
+pub trait StorageBackend: Send + Sync {
+    fn exists(&self, path: &Path) -> bool;
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    /// Adds to the end of `path`, creating it if needed.
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove(&self, path: &Path) -> io::Result<()>;
+    /// Length of `path` in bytes.
+    fn size(&self, path: &Path) -> io::Result<u64>;
+    /// Makes `path` readable by its owner only, where that means anything.
+    fn restrict(&self, path: &Path) -> io::Result<()>;
+}
+
+/// The real data directory.
+pub struct DiskBackend;
+
+impl StorageBackend for DiskBackend {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+    
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+    
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+    
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(contents)
+    }
+    
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+    
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+    
+    fn size(&self, path: &Path) -> io::Result<u64> {
+        fs::metadata(path).map(|metadata| metadata.len())
+    }
+    
+    fn restrict(&self, _path: &Path) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(_path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+/// Files held in a map for the life of the process, for `--demo`. Every clone
+/// of a `Storage` shares the one map, so the auth and todo managers see each
+/// other's writes.
+#[derive(Default)]
+pub struct MemoryBackend {
+    files: Mutex<HashMap<PathBuf, Vec<u8>>>,
+}
+
+impl MemoryBackend {
+    fn files(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Vec<u8>>> {
+        // A panic mid-write can't leave a half-written Vec, so poisoning is harmless
+        self.files.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} does not exist", path.display()))
+}
+
+impl StorageBackend for MemoryBackend {
+    fn exists(&self, path: &Path) -> bool {
+        self.files().contains_key(path)
+    }
+    
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files().get(path).cloned().ok_or_else(|| not_found(path))
+    }
+    
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files().insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+    
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files().entry(path.to_path_buf()).or_default().extend_from_slice(contents);
+        Ok(())
+    }
+    
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let contents = self.read(from)?;
+        self.write(to, &contents)
+    }
+    
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.files().remove(path).map(|_| ()).ok_or_else(|| not_found(path))
+    }
+    
+    fn size(&self, path: &Path) -> io::Result<u64> {
+        self.files().get(path).map(|contents| contents.len() as u64).ok_or_else(|| not_found(path))
+    }
+    
+    fn restrict(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+}
 
 #[derive(Clone)]
 pub struct Storage {
+    backend: Arc<dyn StorageBackend>,
     data_dir: PathBuf,
     users_file: PathBuf,
     todos_file: PathBuf,
     session_file: PathBuf,
+    session_sig_file: PathBuf,
+    session_key_file: PathBuf,
     markdown_file: PathBuf,
+    history_file: PathBuf,
+    config_file: PathBuf,
+    /// Exists once the first-run guide has been shown.
+    onboarded_file: PathBuf,
+    /// Write `todos.json` gzipped, as `todos.json.gz`.
+    compress_todos: bool,
+    /// Shortest id prefix shown in `todos.md`.
+    id_length: usize,
+    /// Order within each section of `todos.md`.
+    markdown_sort: SortKey,
+}
+
+const APP_DIR: &str = "todo-cli";
+const LEGACY_DIR: &str = ".todo-cli";
+const DATA_FILES: [&str; 4] = ["users.json", "todos.json", "session.json", "todos.md"];
+
+/// One line of `history.jsonl`: who did what, and when.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub at: DateTime<Utc>,
+    pub user_id: String,
+    pub action: String,
+    pub detail: String,
 }
 
 impl Storage {
-    pub fn new() -> Result<Self> {
-        let data_dir = dirs::home_dir()
-            .context("Failed to get home directory")?
-            .join(".todo-cli");
+    /// Opens the data store. Data goes under the platform data directory
+    /// (`$XDG_DATA_HOME`, `~/Library/Application Support`, `%APPDATA%`) and
+    /// `config.json` under the platform config directory. `data_dir_override`
+    /// (the `--data-dir` flag) puts everything, config included, in one place.
+    pub fn new(data_dir_override: Option<PathBuf>) -> Result<Self> {
+        let is_default_location = data_dir_override.is_none();
+        let (data_dir, config_dir) = match data_dir_override {
+            Some(dir) => (dir.clone(), dir),
+            None => {
+                let data_dir = dirs::data_dir()
+                    .context("Failed to get data directory")?
+                    .join(APP_DIR);
+                let config_dir = dirs::config_dir()
+                    .context("Failed to get config directory")?
+                    .join(APP_DIR);
+                (data_dir, config_dir)
+            }
+        };
         
-        // Create data directory if it doesn't exist
+        // Create data directories if they don't exist
         fs::create_dir_all(&data_dir)
             .context("Failed to create data directory")?;
+        fs::create_dir_all(&config_dir)
+            .context("Failed to create config directory")?;
         
-        let users_file = data_dir.join("users.json");
-        let todos_file = data_dir.join("todos.json");
-        let session_file = data_dir.join("session.json");
-        let markdown_file = data_dir.join("todos.md");
+        let storage = Self::with_backend(Arc::new(DiskBackend), data_dir, &config_dir);
+        let todos_exist = storage.todos_file.exists() || gz_path(&storage.todos_file).exists();
+        // Only the platform default location takes over `~/.todo-cli`; an
+        // explicit `--data-dir` is left as the caller made it.
+        if is_default_location && !storage.users_file.exists() && !todos_exist {
+            Self::migrate_legacy_dir(&storage.data_dir, &storage.config_file)?;
+        }
         
-        Ok(Self {
+        storage.seed_empty_stores()?;
+        Ok(storage)
+    }
+    
+    /// A store that only lives in this process's memory, for `--demo`. Writes
+    /// succeed but are gone when the process exits, and no config is read.
+    pub fn in_memory() -> Result<Self> {
+        let dir = PathBuf::from("(in memory)");
+        let storage = Self::with_backend(Arc::new(MemoryBackend::default()), dir.clone(), &dir);
+        storage.seed_empty_stores()?;
+        Ok(storage)
+    }
+    
+    fn with_backend(backend: Arc<dyn StorageBackend>, data_dir: PathBuf, config_dir: &Path) -> Self {
+        Self {
+            backend,
+            users_file: data_dir.join("users.json"),
+            todos_file: data_dir.join("todos.json"),
+            session_file: data_dir.join("session.json"),
+            session_sig_file: data_dir.join("session.sig"),
+            session_key_file: data_dir.join("session.key"),
+            markdown_file: data_dir.join("todos.md"),
+            history_file: data_dir.join("history.jsonl"),
+            config_file: config_dir.join("config.json"),
+            onboarded_file: data_dir.join("onboarded"),
             data_dir,
-            users_file,
-            todos_file,
-            session_file,
-            markdown_file,
-        })
+            compress_todos: false,
+            id_length: 8,
+            markdown_sort: SortKey::Due,
+        }
     }
     
-    pub fn load_users(&self) -> Result<HashMap<String, User>> {
-        if !self.users_file.exists() {
-            return Ok(HashMap::new());
+    /// Seeds empty stores so a fresh install reads the same as an existing one.
+    fn seed_empty_stores(&self) -> Result<()> {
+        self.ensure_file(&self.users_file, &Self::versioned("users", &HashMap::<String, User>::new())?)?;
+        if !self.backend.exists(&gz_path(&self.todos_file)) {
+            self.ensure_file(&self.todos_file, &Self::versioned("todos", &HashMap::<String, Todo>::new())?)?;
+        }
+        Ok(())
+    }
+    
+    /// Moves data from the old `~/.todo-cli` location into the new directories
+    /// on first run after upgrading.
+    fn migrate_legacy_dir(data_dir: &Path, config_file: &Path) -> Result<()> {
+        let legacy_dir = match dirs::home_dir() {
+            Some(home) => home.join(LEGACY_DIR),
+            None => return Ok(()),
+        };
+        if !legacy_dir.is_dir() || legacy_dir == data_dir {
+            return Ok(());
         }
         
-        let content = fs::read_to_string(&self.users_file)
-            .context("Failed to read users file")?;
+        for name in DATA_FILES {
+            Self::move_file(&legacy_dir.join(name), &data_dir.join(name))?;
+        }
+        Self::move_file(&legacy_dir.join("config.json"), config_file)?;
         
-        let users: HashMap<String, User> = serde_json::from_str(&content)
-            .context("Failed to parse users file")?;
+        // Only succeeds once nothing is left behind
+        let _ = fs::remove_dir(&legacy_dir);
         
-        Ok(users)
+        eprintln!("Moved existing data from {} to {}", legacy_dir.display(), data_dir.display());
+        Ok(())
     }
     
-    pub fn save_users(&self, users: &HashMap<String, User>) -> Result<()> {
-        let content = serde_json::to_string_pretty(users)
-            .context("Failed to serialize users")?;
+    fn move_file(from: &Path, to: &Path) -> Result<()> {
+        if !from.exists() || to.exists() {
+            return Ok(());
+        }
+        // Rename fails across filesystems, so fall back to copy + remove
+        if fs::rename(from, to).is_err() {
+            fs::copy(from, to)
+                .with_context(|| format!("Failed to copy {}", from.display()))?;
+            fs::remove_file(from)
+                .with_context(|| format!("Failed to remove {}", from.display()))?;
+        }
+        Ok(())
+    }
+    
+    fn ensure_file(&self, path: &Path, initial: &str) -> Result<()> {
+        if !self.backend.exists(path) {
+            self.backend.write(path, initial.as_bytes())
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+        }
+        Ok(())
+    }
+    
+    pub fn has_onboarded(&self) -> bool {
+        self.backend.exists(&self.onboarded_file)
+    }
+    
+    /// Records that the first-run guide was shown, so it isn't offered again.
+    pub fn mark_onboarded(&self) -> Result<()> {
+        self.backend.write(&self.onboarded_file, Utc::now().to_rfc3339().as_bytes())
+            .context("Failed to write onboarding marker")
+    }
+    
+    /// Turns gzip for `todos.json` on or off. Either form is read regardless,
+    /// and the next save converts to the chosen one.
+    pub fn set_compression(&mut self, compress: bool) {
+        self.compress_todos = compress;
+    }
+    
+    /// Minimum id length for `todos.md`, from `id_display_length`.
+    pub fn set_id_length(&mut self, length: usize) {
+        self.id_length = length;
+    }
+    
+    /// How `todos.md` orders each section, from `markdown_sort`.
+    pub fn set_markdown_sort(&mut self, sort: SortKey) {
+        self.markdown_sort = sort;
+    }
+    
+    pub fn markdown_sort(&self) -> SortKey {
+        self.markdown_sort
+    }
+    
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+    
+    pub fn config_file(&self) -> &Path {
+        &self.config_file
+    }
+    
+    /// The main data files with their sizes, `None` for ones not written yet.
+    /// `todos.json` is listed as `todos.json.gz` when that's the copy in use.
+    pub fn file_sizes(&self) -> Vec<(PathBuf, Option<u64>)> {
+        let gz = gz_path(&self.todos_file);
+        let todos_file = if self.backend.exists(&gz) && (!self.backend.exists(&self.todos_file) || self.compresses(&self.todos_file)) {
+            gz
+        } else {
+            self.todos_file.clone()
+        };
+        [self.users_file.clone(), todos_file, self.markdown_file.clone(), self.history_file.clone()]
+            .into_iter()
+            .map(|path| {
+                let size = self.backend.size(&path).ok();
+                (path, size)
+            })
+            .collect()
+    }
+    
+    /// `path` as UTF-8 text.
+    fn read_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.backend.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    
+    pub fn load_config(&self) -> Result<Config> {
+        if !self.backend.exists(&self.config_file) {
+            return Ok(Config::default());
+        }
+        
+        let content = self.read_string(&self.config_file)
+            .context("Failed to read config file")?;
         
-        fs::write(&self.users_file, content)
+        if content.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        
+        let config: Config = serde_json::from_str(&content)
+            .context("Failed to parse config file")?;
+        
+        Ok(config)
+    }
+    
+    pub fn load_users(&self) -> Result<HashMap<String, User>> {
+        self.load_versioned(&self.users_file, "users", migrations::USERS)
+    }
+    
+    pub fn save_users(&self, users: &HashMap<String, User>) -> Result<()> {
+        self.backend.write(&self.users_file, Self::versioned("users", users)?.as_bytes())
             .context("Failed to write users file")?;
         
         Ok(())
     }
     
     pub fn load_todos(&self) -> Result<HashMap<String, Todo>> {
-        if !self.todos_file.exists() {
-            return Ok(HashMap::new());
+        self.load_versioned(&self.todos_file, "todos", migrations::TODOS)
+    }
+    
+    pub fn save_todos(&self, todos: &HashMap<String, Todo>) -> Result<()> {
+        self.write_data_file(&self.todos_file, &Self::versioned("todos", todos)?)
+            .context("Failed to write todos file")?;
+        
+        Ok(())
+    }
+    
+    /// Reads a map stored under `key` in a versioned data file. Files written
+    /// by older releases are upgraded first; the original is kept next to it
+    /// as `<name>.v<N>.bak` and the upgraded form written back.
+    fn load_versioned<T: DeserializeOwned + Default>(&self, path: &Path, key: &str, steps: &[Migration]) -> Result<T> {
+        let (content, source) = match self.read_data_file(path)
+            .with_context(|| format!("Failed to read {} file", key))? {
+            Some(found) => found,
+            None => return Ok(T::default()),
+        };
+        
+        if content.trim().is_empty() {
+            return Ok(T::default());
         }
         
-        let content = fs::read_to_string(&self.todos_file)
-            .context("Failed to read todos file")?;
+        let value: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {} file", key))?;
+        let (mut value, from) = migrations::upgrade(value, steps)?;
+        
+        if from < migrations::CURRENT_VERSION {
+            let backup = PathBuf::from(format!("{}.v{}.bak", source.display(), from));
+            self.backend.copy(&source, &backup)
+                .with_context(|| format!("Failed to back up {}", source.display()))?;
+            self.write_data_file(path, &serde_json::to_string_pretty(&value)?)
+                .with_context(|| format!("Failed to write {} file", key))?;
+            eprintln!(
+                "Upgraded {} to schema version {} (backup at {})",
+                source.display(),
+                migrations::CURRENT_VERSION,
+                backup.display()
+            );
+        }
         
-        let todos: HashMap<String, Todo> = serde_json::from_str(&content)
-            .context("Failed to parse todos file")?;
+        let data = serde_json::from_value(value[key].take())
+            .with_context(|| format!("Failed to parse {} file", key))?;
         
-        Ok(todos)
+        Ok(data)
     }
     
-    pub fn save_todos(&self, todos: &HashMap<String, Todo>) -> Result<()> {
-        let content = serde_json::to_string_pretty(todos)
-            .context("Failed to serialize todos")?;
-        
-        fs::write(&self.todos_file, content)
-            .context("Failed to write todos file")?;
+    /// Reads `path`, or its `.gz` sibling, returning the text and which file
+    /// it came from. If both exist, the one matching the compression setting wins.
+    fn read_data_file(&self, path: &Path) -> Result<Option<(String, PathBuf)>> {
+        let gz = gz_path(path);
+        let use_gz = self.backend.exists(&gz) && (!self.backend.exists(path) || self.compresses(path));
         
+        if use_gz {
+            let mut content = String::new();
+            GzDecoder::new(self.backend.read(&gz)?.as_slice())
+                .read_to_string(&mut content)
+                .with_context(|| format!("Failed to decompress {}", gz.display()))?;
+            Ok(Some((content, gz)))
+        } else if self.backend.exists(path) {
+            Ok(Some((self.read_string(path)?, path.to_path_buf())))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Writes `path` plainly or gzipped per the compression setting, then
+    /// removes the other form so it can't be read by mistake.
+    fn write_data_file(&self, path: &Path, content: &str) -> Result<()> {
+        let gz = gz_path(path);
+        if self.compresses(path) {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content.as_bytes())?;
+            self.backend.write(&gz, &encoder.finish()?)?;
+            if self.backend.exists(path) {
+                self.backend.remove(path)?;
+            }
+        } else {
+            self.backend.write(path, content.as_bytes())?;
+            if self.backend.exists(&gz) {
+                self.backend.remove(&gz)?;
+            }
+        }
         Ok(())
     }
     
+    fn compresses(&self, path: &Path) -> bool {
+        self.compress_todos && path == self.todos_file
+    }
+    
+    /// `data` in the current versioned envelope, ready to write.
+    fn versioned<T: Serialize>(key: &str, data: &T) -> Result<String> {
+        let mut file = serde_json::Map::new();
+        file.insert("schema_version".to_string(), migrations::CURRENT_VERSION.into());
+        file.insert(key.to_string(), serde_json::to_value(data)?);
+        
+        serde_json::to_string_pretty(&file)
+            .with_context(|| format!("Failed to serialize {}", key))
+    }
+    
+    /// Reads the saved session, if its signature checks out. A session that
+    /// was edited by hand (or has no signature) is deleted instead, so the
+    /// user has to log in again.
     pub fn load_session(&self) -> Result<Option<Session>> {
-        if !self.session_file.exists() {
+        if !self.backend.exists(&self.session_file) {
             return Ok(None);
         }
         
-        let content = fs::read_to_string(&self.session_file)
+        let content = self.read_string(&self.session_file)
             .context("Failed to read session file")?;
         
+        if content.trim().is_empty() {
+            return Ok(None);
+        }
+        
+        if !self.session_signature_matches(&content)? {
+            eprintln!("Session file failed its integrity check; please log in again");
+            self.clear_session()?;
+            return Ok(None);
+        }
+        
         let session: Session = serde_json::from_str(&content)
             .context("Failed to parse session file")?;
         
@@ -108,41 +594,105 @@ impl Storage {
         let content = serde_json::to_string_pretty(session)
             .context("Failed to serialize session")?;
         
-        fs::write(&self.session_file, content)
+        let signature = self.session_mac(&content)?.finalize().into_bytes();
+        self.backend.write(&self.session_sig_file, to_hex(&signature).as_bytes())
+            .context("Failed to write session signature")?;
+        self.backend.write(&self.session_file, content.as_bytes())
             .context("Failed to write session file")?;
         
         Ok(())
     }
     
     pub fn clear_session(&self) -> Result<()> {
-        if self.session_file.exists() {
-            fs::remove_file(&self.session_file)
-                .context("Failed to remove session file")?;
+        for file in [&self.session_file, &self.session_sig_file] {
+            if self.backend.exists(file) {
+                self.backend.remove(file)
+                    .context("Failed to remove session file")?;
+            }
         }
         Ok(())
     }
     
-    pub fn append_to_markdown(&self, todo: &Todo) -> Result<()> {
-        let markdown_content = self.format_todo_markdown(todo);
-        
-        if !self.markdown_file.exists() {
-            let header = format!("# Todo List\n\nGenerated by Todo CLI\n\n---\n\n");
-            fs::write(&self.markdown_file, header)
-                .context("Failed to create markdown file")?;
+    /// Whether a saved session carries a valid signature (trivially true
+    /// when there is none). Unlike `load_session`, never deletes anything.
+    pub fn session_is_signed(&self) -> Result<bool> {
+        match self.read_string(&self.session_file) {
+            Ok(content) if !content.trim().is_empty() => self.session_signature_matches(&content),
+            _ => Ok(true),
+        }
+    }
+    
+    fn session_signature_matches(&self, content: &str) -> Result<bool> {
+        let expected = match self.read_string(&self.session_sig_file) {
+            Ok(hex) => match from_hex(hex.trim()) {
+                Some(bytes) => bytes,
+                None => return Ok(false),
+            },
+            Err(_) => return Ok(false),
+        };
+        // verify_slice compares in constant time
+        Ok(self.session_mac(content)?.verify_slice(&expected).is_ok())
+    }
+    
+    /// HMAC-SHA256 over the session JSON, keyed by a random per-install
+    /// secret kept in `session.key` (readable only by the owner on Unix).
+    fn session_mac(&self, content: &str) -> Result<Hmac<Sha256>> {
+        let key = self.session_key()?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key)
+            .context("Failed to initialise session signing")?;
+        mac.update(content.as_bytes());
+        Ok(mac)
+    }
+    
+    fn session_key(&self) -> Result<Vec<u8>> {
+        if let Ok(hex) = self.read_string(&self.session_key_file) {
+            if let Some(key) = from_hex(hex.trim()).filter(|k| !k.is_empty()) {
+                return Ok(key);
+            }
         }
         
-        let mut existing_content = fs::read_to_string(&self.markdown_file)
-            .context("Failed to read markdown file")?;
-        
-        existing_content.push_str(&markdown_content);
-        
-        fs::write(&self.markdown_file, existing_content)
-            .context("Failed to write to markdown file")?;
+        // Two v4 UUIDs give 244 random bits from the OS generator
+        let key: Vec<u8> = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()].iter()
+            .flat_map(|id| *id.as_bytes())
+            .collect();
+        self.backend.write(&self.session_key_file, to_hex(&key).as_bytes())
+            .context("Failed to write session key")?;
+        self.backend.restrict(&self.session_key_file)
+            .context("Failed to restrict session key permissions")?;
+        Ok(key)
+    }
+    
+    /// Adds a line to the append-only history log.
+    pub fn append_history(&self, entry: &HistoryEntry) -> Result<()> {
+        let line = format!("{}\n", serde_json::to_string(entry)?);
+        self.backend.append(&self.history_file, line.as_bytes())
+            .context("Failed to write history log")?;
         
         Ok(())
     }
     
-    pub fn update_markdown_todo(&self, updated_todo: &Todo) -> Result<()> {
+    /// Every history entry, oldest first. A missing log is an empty one.
+    pub fn read_history(&self) -> Result<Vec<HistoryEntry>> {
+        if !self.backend.exists(&self.history_file) {
+            return Ok(Vec::new());
+        }
+        let content = self.read_string(&self.history_file)
+            .context("Failed to read history log")?;
+        content.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse line {} of the history log", i + 1)))
+            .collect()
+    }
+    
+    pub fn append_to_markdown(&self, _todo: &Todo) -> Result<()> {
+        // Rebuilt rather than appended so new todos land in order
+        self.regenerate_markdown()?;
+        Ok(())
+    }
+    
+    pub fn update_markdown_todo(&self, _updated_todo: &Todo) -> Result<()> {
         self.regenerate_markdown()?;
         Ok(())
     }
@@ -152,7 +702,55 @@ impl Storage {
         Ok(())
     }
     
-    fn regenerate_markdown(&self) -> Result<()> {
+    pub fn regenerate_markdown(&self) -> Result<()> {
+        self.regenerate_markdown_sorted(self.markdown_sort)
+    }
+    
+    /// Rewrites the markdown export from the JSON data: users alphabetically,
+    /// each split into pending and completed sections ordered by `sort`.
+    pub fn regenerate_markdown_sorted(&self, sort: SortKey) -> Result<()> {
+        let content = self.render_markdown(sort)?;
+        self.backend.write(&self.markdown_file, content.as_bytes())
+            .context("Failed to write markdown file")?;
+        
+        Ok(())
+    }
+    
+    /// Compares `todos.md` with what the JSON data says it should contain, for
+    /// example after a crash between saving todos and rewriting the markdown.
+    /// Lines are compared as a multiset, so a file sorted differently by
+    /// `regenerate --sort` is not reported, and the generation timestamp is
+    /// ignored.
+    pub fn verify_markdown(&self) -> Result<MarkdownDrift> {
+        let expected = self.render_markdown(self.markdown_sort)?;
+        let actual = if self.backend.exists(&self.markdown_file) {
+            self.read_string(&self.markdown_file)
+                .context("Failed to read markdown file")?
+        } else {
+            String::new()
+        };
+        
+        let mut counts: HashMap<&str, i64> = HashMap::new();
+        for line in comparable_lines(&expected) {
+            *counts.entry(line).or_default() += 1;
+        }
+        for line in comparable_lines(&actual) {
+            *counts.entry(line).or_default() -= 1;
+        }
+        
+        let mut drift = MarkdownDrift::default();
+        for (line, count) in counts {
+            let bucket = if count > 0 { &mut drift.missing } else { &mut drift.unexpected };
+            for _ in 0..count.abs() {
+                bucket.push(line.to_string());
+            }
+        }
+        drift.missing.sort();
+        drift.unexpected.sort();
+        Ok(drift)
+    }
+    
+    fn render_markdown(&self, sort: SortKey) -> Result<String> {
         let todos = self.load_todos()?;
         let users = self.load_users()?;
         
@@ -161,30 +759,39 @@ impl Storage {
         content.push_str(&format!("Generated by Todo CLI on {}\n\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
         content.push_str("---\n\n");
         
+        let id_length = todo::unique_prefix_len(todos.keys().map(String::as_str), self.id_length);
+        
         // Group todos by user
         let mut todos_by_user: HashMap<String, Vec<&Todo>> = HashMap::new();
         for todo in todos.values() {
             todos_by_user.entry(todo.user_id.clone()).or_default().push(todo);
         }
         
-        for (user_id, user_todos) in todos_by_user {
-            if let Some(user) = users.get(&user_id) {
-                content.push_str(&format!("## {} ({})\n\n", user.username, user.email));
+        let mut owners: Vec<&User> = todos_by_user.keys()
+            .filter_map(|user_id| users.get(user_id))
+            .collect();
+        owners.sort_by(|a, b| a.username.cmp(&b.username));
+        
+        for user in owners {
+            if let Some(user_todos) = todos_by_user.get(&user.id) {
+                content.push_str(&format!("## {} ({})\n\n", escape_inline(&user.username), escape_inline(&user.email)));
                 
                 // Separate by status
-                let pending: Vec<&Todo> = user_todos.iter()
+                let mut pending: Vec<&Todo> = user_todos.iter()
                     .filter(|t| t.status == Status::Pending)
                     .cloned()
                     .collect();
-                let completed: Vec<&Todo> = user_todos.iter()
+                let mut completed: Vec<&Todo> = user_todos.iter()
                     .filter(|t| t.status == Status::Completed)
                     .cloned()
                     .collect();
+                sort_todos(&mut pending, sort);
+                sort_todos(&mut completed, sort);
                 
                 if !pending.is_empty() {
                     content.push_str("### 📋 Pending Tasks\n\n");
                     for todo in pending {
-                        content.push_str(&self.format_todo_markdown(todo));
+                        content.push_str(&self.format_todo_markdown(todo, id_length));
                     }
                     content.push('\n');
                 }
@@ -192,7 +799,7 @@ impl Storage {
                 if !completed.is_empty() {
                     content.push_str("### ✅ Completed Tasks\n\n");
                     for todo in completed {
-                        content.push_str(&self.format_todo_markdown(todo));
+                        content.push_str(&self.format_todo_markdown(todo, id_length));
                     }
                     content.push('\n');
                 }
@@ -201,13 +808,10 @@ impl Storage {
             }
         }
         
-        fs::write(&self.markdown_file, content)
-            .context("Failed to write markdown file")?;
-        
-        Ok(())
+        Ok(content)
     }
     
-    fn format_todo_markdown(&self, todo: &Todo) -> String {
+    fn format_todo_markdown(&self, todo: &Todo, id_length: usize) -> String {
         let status_checkbox = match todo.status {
             Status::Pending => "- [ ]",
             Status::Completed => "- [x]",
@@ -219,22 +823,72 @@ impl Storage {
             Priority::High => "🔴",
         };
         
-        let id_short = &todo.id[..8];
+        let id_short = todo::abbreviate(&todo.id, id_length);
         let mut content = format!("{} {} **{}** `{}`\n", 
-            status_checkbox, priority_emoji, todo.title, id_short);
+            status_checkbox, priority_emoji, escape_inline(&todo.title), id_short);
         
+        // Every line of the description stays inside the quote
         if let Some(ref description) = todo.description {
-            content.push_str(&format!("  > {}\n", description));
+            for line in escape_markdown(description).split('\n') {
+                content.push_str(&format!("  > {}\n", line));
+            }
+        }
+        
+        if let Some(ref project) = todo.project {
+            content.push_str(&format!("  📁 {}\n", escape_inline(project)));
+        }
+        
+        if let Some(ref waiting) = todo.waiting {
+            match waiting.on {
+                Some(ref on) => content.push_str(&format!("  ⏸ Waiting on {}\n", escape_inline(on))),
+                None => content.push_str("  ⏸ Waiting\n"),
+            }
+        }
+        
+        if todo.someday {
+            content.push_str("  🌱 Someday/maybe\n");
+        }
+        
+        if !todo.tags.is_empty() {
+            let tags: Vec<String> = todo.tags.iter().map(|t| format!("`#{}`", t)).collect();
+            content.push_str(&format!("  🏷️ {}\n", tags.join(" ")));
+        }
+        
+        for subtask in &todo.subtasks {
+            let checkbox = if subtask.done { "[x]" } else { "[ ]" };
+            content.push_str(&format!("  - {} {}\n", checkbox, escape_inline(&subtask.title)));
+        }
+        
+        for milestone in &todo.milestones {
+            let checkbox = if milestone.done { "[x]" } else { "[ ]" };
+            content.push_str(&format!("  🚩 {} {} ({})\n", checkbox, escape_inline(&milestone.label), milestone.date.format("%Y-%m-%d")));
         }
         
         if let Some(due_date) = todo.due_date {
-            let due_datetime = DateTime::<Local>::from_naive_utc_and_offset(due_date, *Local::now().offset());
-            content.push_str(&format!("  📅 **Due:** {}\n", due_datetime.format("%Y-%m-%d %H:%M")));
+            let zone = todo.due_tz.as_deref().map(|tz| format!(" {}", tz)).unwrap_or_default();
+            content.push_str(&format!("  📅 **Due:** {}{}\n", due_date.format("%Y-%m-%d %H:%M"), zone));
+        }
+        
+        if let Some(minutes) = todo.estimate_minutes {
+            content.push_str(&format!("  ⏱ **Estimate:** {}\n", dates::format_minutes(minutes)));
+        }
+        
+        if let Some(recurrence) = todo.recurrence {
+            content.push_str(&format!("  🔁 **Repeats:** {}\n", recurrence.label()));
         }
         
         content.push_str(&format!("  🕒 **Created:** {}\n", 
             todo.created_at.format("%Y-%m-%d %H:%M")));
         
+        if let Some(done_at) = todo.completed_time() {
+            content.push_str(&format!("  ✅ **Completed:** {}\n", 
+                done_at.format("%Y-%m-%d %H:%M")));
+        }
+        
+        if let Some(note) = &todo.completion_note {
+            content.push_str(&format!("  🗒 **Outcome:** {}\n", escape_inline(note)));
+        }
+        
         if todo.created_at != todo.updated_at {
             content.push_str(&format!("  🔄 **Updated:** {}\n", 
                 todo.updated_at.format("%Y-%m-%d %H:%M")));
@@ -243,4 +897,174 @@ impl Storage {
         content.push('\n');
         content
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// A directory under the system temp dir that doesn't exist yet.
+    fn fresh_data_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("todo-cli-test-{}", uuid::Uuid::new_v4()))
+    }
+    
+    #[test]
+    fn a_fresh_data_dir_is_created_with_empty_stores() {
+        let dir = fresh_data_dir();
+        let storage = Storage::new(Some(dir.clone())).unwrap();
+        assert!(dir.join("users.json").is_file());
+        assert!(dir.join("todos.json").is_file());
+        assert!(storage.load_users().unwrap().is_empty());
+        assert!(storage.load_todos().unwrap().is_empty());
+        assert!(storage.load_session().unwrap().is_none());
+        
+        // Opening it again leaves what is there alone
+        let users = fs::read_to_string(dir.join("users.json")).unwrap();
+        let storage = Storage::new(Some(dir.clone())).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("users.json")).unwrap(), users);
+        assert!(storage.load_todos().unwrap().is_empty());
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn missing_files_load_as_empty() {
+        let dir = fresh_data_dir();
+        let storage = Storage::new(Some(dir.clone())).unwrap();
+        fs::remove_file(dir.join("users.json")).unwrap();
+        fs::remove_file(dir.join("todos.json")).unwrap();
+        assert!(storage.load_users().unwrap().is_empty());
+        assert!(storage.load_todos().unwrap().is_empty());
+        assert!(storage.load_session().unwrap().is_none());
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    /// An in-memory store with one user, `alice`, owning `todos`.
+    fn store_with(todos: &[Todo]) -> Storage {
+        let storage = Storage::in_memory().unwrap();
+        let user = User {
+            id: "alice".to_string(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            password_hash: String::new(),
+            created_at: Utc::now(),
+            last_login: None,
+            settings: Default::default(),
+            is_admin: true,
+            failed_logins: Default::default(),
+        };
+        storage.save_users(&HashMap::from([(user.id.clone(), user)])).unwrap();
+        storage.save_todos(&todos.iter().map(|t| (t.id.clone(), t.clone())).collect()).unwrap();
+        storage
+    }
+    
+    fn todo(title: &str) -> Todo {
+        Todo::new(title.to_string(), None, Priority::Medium, None, "alice".to_string())
+    }
+    
+    #[test]
+    fn markdown_that_fell_behind_the_json_is_reported_and_repaired() {
+        let mut todo = todo("Write report");
+        let storage = store_with(std::slice::from_ref(&todo));
+        storage.regenerate_markdown().unwrap();
+        assert!(storage.verify_markdown().unwrap().is_clean());
+        
+        // The JSON save went through, the markdown rewrite never happened
+        todo.title = "Send report".to_string();
+        storage.save_todos(&HashMap::from([(todo.id.clone(), todo)])).unwrap();
+        let drift = storage.verify_markdown().unwrap();
+        assert_eq!(drift.missing.len(), 1);
+        assert!(drift.missing[0].contains("Send report"));
+        assert_eq!(drift.unexpected.len(), 1);
+        assert!(drift.unexpected[0].contains("Write report"));
+        
+        storage.regenerate_markdown().unwrap();
+        assert!(storage.verify_markdown().unwrap().is_clean());
+    }
+    
+    #[test]
+    fn a_missing_markdown_file_counts_as_drift() {
+        let storage = store_with(&[todo("Anything")]);
+        let drift = storage.verify_markdown().unwrap();
+        assert!(!drift.is_clean());
+        assert!(drift.unexpected.is_empty());
+    }
+    
+    /// Undoes `escape_markdown`: a backslash keeps the next character as is.
+    fn unescape(text: &str) -> String {
+        let mut plain = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            plain.push(if c == '\\' { chars.next().unwrap() } else { c });
+        }
+        plain
+    }
+    
+    #[test]
+    fn markdown_syntax_in_user_text_is_escaped_reversibly() {
+        assert_eq!(escape_markdown("- [x] trick"), r"\- \[x\] trick");
+        assert_eq!(escape_markdown("12. not a list"), r"12\. not a list");
+        for text in ["- [x] trick", "# heading", "**bold** and `code`", "1) first\n  + second", r"C:\path\_file", "a | b ~ c <d>"] {
+            assert_eq!(unescape(&escape_markdown(text)), text, "{text:?}");
+        }
+    }
+    
+    #[test]
+    fn tricky_titles_and_descriptions_stay_inside_their_todo() {
+        let mut todo = todo("- [x] trick");
+        todo.description = Some("# not a heading\n- [ ] nor a checkbox".to_string());
+        let storage = store_with(&[todo]);
+        storage.regenerate_markdown().unwrap();
+        let markdown = storage.read_string(&storage.markdown_file).unwrap();
+        
+        let items: Vec<&str> = markdown.lines().filter(|l| l.starts_with("- [")).collect();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].starts_with(r"- [ ] 🟡 **\- \[x\] trick**"));
+        assert!(markdown.contains("  > \\# not a heading\n  > \\- \\[ \\] nor a checkbox\n"));
+        assert!(storage.verify_markdown().unwrap().is_clean());
+    }
+    
+    fn session() -> Session {
+        let now = Utc::now();
+        Session {
+            user_id: "alice".to_string(),
+            created_at: now,
+            expires_at: now + chrono::Duration::days(7),
+            last_activity: now,
+            context: None,
+        }
+    }
+    
+    #[test]
+    fn a_signed_session_loads_back() {
+        let storage = Storage::in_memory().unwrap();
+        storage.save_session(&session()).unwrap();
+        assert_eq!(storage.load_session().unwrap().unwrap().user_id, "alice");
+        assert!(storage.session_is_signed().unwrap());
+    }
+    
+    #[test]
+    fn an_edited_session_is_rejected_and_cleared() {
+        let storage = Storage::in_memory().unwrap();
+        storage.save_session(&session()).unwrap();
+        
+        // Push the expiry out by hand, leaving the signature as it was
+        let content = storage.read_string(&storage.session_file).unwrap();
+        let mut edited: Value = serde_json::from_str(&content).unwrap();
+        edited["expires_at"] = Value::from("2999-01-01T00:00:00Z");
+        storage.backend.write(&storage.session_file, edited.to_string().as_bytes()).unwrap();
+        
+        assert!(!storage.session_is_signed().unwrap());
+        assert!(storage.load_session().unwrap().is_none());
+        assert!(!storage.backend.exists(&storage.session_file));
+        assert!(!storage.backend.exists(&storage.session_sig_file));
+    }
+    
+    #[test]
+    fn a_session_without_its_signature_is_rejected() {
+        let storage = Storage::in_memory().unwrap();
+        storage.save_session(&session()).unwrap();
+        storage.backend.remove(&storage.session_sig_file).unwrap();
+        assert!(storage.load_session().unwrap().is_none());
+    }
+}
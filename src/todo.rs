@@ -1,12 +1,144 @@
-use anyhow::{Result, anyhow};
-use chrono::{DateTime, Utc, NaiveDateTime};
+use chrono::{DateTime, Duration, Local, Months, Utc, NaiveDateTime};
+use regex::{Regex, RegexBuilder};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use thiserror::Error;
 use uuid::Uuid;
 
+use crate::dates::{self, Deferral};
 use crate::storage::Storage;
+use crate::webhook::{Event, Webhook};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Error)]
+pub enum TodoError {
+    #[error("Todo not found")]
+    NotFound,
+    #[error("Subtask {0} not found")]
+    SubtaskNotFound(usize),
+    #[error("Milestone {0} not found")]
+    MilestoneNotFound(usize),
+    #[error("Todo is not completed")]
+    NotCompleted,
+    #[error("Todo is already completed")]
+    AlreadyCompleted,
+    /// `defer` to a date before the current due date.
+    #[error("That would make the todo due earlier; use edit to change it")]
+    DeferBackwards,
+    /// A snooze that would already be over.
+    #[error("Snooze time must be in the future")]
+    SnoozeInPast,
+    /// Someone the todo is shared with tried an owner-only action.
+    #[error("Only the owner can {0} this todo")]
+    OwnerOnly(&'static str),
+    #[error("You already own this todo")]
+    AlreadyOwner,
+    /// An id prefix that fits more than one todo.
+    #[error("'{0}' matches more than one todo; type more of the id")]
+    AmbiguousReference(String),
+    #[error("There is no todo number {0} in the list")]
+    NoSuchPosition(usize),
+    #[error("Upsert requires a key")]
+    MissingKey,
+    /// `add --key` without `--upsert` for a key the user already has.
+    #[error("A todo with key '{0}' already exists; use --upsert to update it")]
+    DuplicateKey(String),
+    #[error("Invalid status: {0}. Use 'pending' or 'completed'")]
+    InvalidStatus(String),
+    #[error("Invalid priority: {0}. Use 'low', 'medium', or 'high'")]
+    InvalidPriority(String),
+    #[error("Invalid sort: {0}. Use 'priority', 'due', 'created', or 'updated'")]
+    InvalidSort(String),
+    #[error("Invalid recurrence: {0}. Use 'daily', 'weekly', or 'monthly'")]
+    InvalidRecurrence(String),
+    #[error("Invalid field: {0}. Use 'title', 'description', 'tags', or 'subtasks'")]
+    InvalidSearchField(String),
+    #[error("Invalid regular expression: {0}")]
+    InvalidPattern(String),
+    #[error("Title cannot be empty")]
+    EmptyTitle,
+    /// A required piece of text, such as a subtask title, was blank.
+    #[error("{0} cannot be empty")]
+    EmptyField(&'static str),
+    /// Subtasks and milestones are numbered from 1 in the listing.
+    #[error("{0} numbers start at 1")]
+    ZeroNumber(&'static str),
+    /// A bulk command given nothing to pick its todos by.
+    #[error("{0}")]
+    NothingSelected(&'static str),
+    /// Adding would put the owner over `max_todos_per_user`.
+    #[error("You already have the maximum of {0} open todos; complete, archive or delete some first")]
+    TooManyTodos(usize),
+    /// Reading or writing the todo files failed.
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+pub type Result<T, E = TodoError> = std::result::Result<T, E>;
+
+/// Canonical form for user-typed enum values, shared by every `from_string`
+/// so CLI flags, prompts and imports all accept the same input.
+fn normalize(s: &str) -> String {
+    s.trim().to_lowercase()
+}
+
+/// The title to store for user input: trimmed, and never blank. Every way
+/// of naming a todo (flags, prompts, edit, import) goes through this.
+pub fn validate_title(title: &str) -> Result<String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(TodoError::EmptyTitle);
+    }
+    Ok(title.to_string())
+}
+
+/// Inline fields pulled out of a quick-add title such as
+/// `Pay rent !high #finance @home due:tomorrow`. Values are left as typed,
+/// for the caller to validate like the matching flags.
+#[derive(Debug, Default)]
+pub struct QuickAdd {
+    /// The words that weren't tokens.
+    pub title: String,
+    pub priority: Option<String>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub due: Option<String>,
+}
+
+impl QuickAdd {
+    /// Splits on whitespace; a bare `!`, `#` or `@` is an ordinary word.
+    /// When a token repeats, the last one wins.
+    pub fn parse(input: &str) -> Self {
+        let mut quick = QuickAdd::default();
+        let mut words = Vec::new();
+        for word in input.split_whitespace() {
+            if let Some(priority) = word.strip_prefix('!').filter(|p| !p.is_empty()) {
+                quick.priority = Some(priority.to_string());
+            } else if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+                quick.tags.push(tag.to_string());
+            } else if let Some(project) = word.strip_prefix('@').filter(|p| !p.is_empty()) {
+                quick.project = Some(project.to_string());
+            } else if let Some(due) = word.strip_prefix("due:").filter(|d| !d.is_empty()) {
+                quick.due = Some(due.to_string());
+            } else {
+                words.push(word);
+            }
+        }
+        quick.title = words.join(" ");
+        quick
+    }
+}
+
+/// Tags are stored lowercase without a leading `#`, so `#Work` and `work` match.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum Status {
     Pending,
     Completed,
@@ -14,33 +146,220 @@ pub enum Status {
 
 impl Status {
     pub fn from_string(s: &str) -> Result<Self> {
-        match s.to_lowercase().as_str() {
-            "pending" | "p" => Ok(Status::Pending),
-            "completed" | "complete" | "done" | "c" => Ok(Status::Completed),
-            _ => Err(anyhow!("Invalid status: {}. Use 'pending' or 'completed'", s)),
+        match normalize(s).as_str() {
+            "pending" | "p" | "todo" | "open" => Ok(Status::Pending),
+            "completed" | "complete" | "done" | "finished" | "c" => Ok(Status::Completed),
+            _ => Err(TodoError::InvalidStatus(s.to_string())),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
 pub enum Priority {
+    #[serde(alias = "low")]
     Low,
+    #[serde(alias = "medium")]
     Medium,
+    #[serde(alias = "high")]
     High,
 }
 
 impl Priority {
     pub fn from_string(s: &str) -> Result<Self> {
-        match s.to_lowercase().as_str() {
+        match normalize(s).as_str() {
             "low" | "l" => Ok(Priority::Low),
-            "medium" | "med" | "m" => Ok(Priority::Medium),
-            "high" | "h" => Ok(Priority::High),
-            _ => Err(anyhow!("Invalid priority: {}. Use 'low', 'medium', or 'high'", s)),
+            "medium" | "med" | "m" | "normal" => Ok(Priority::Medium),
+            "high" | "h" | "urgent" => Ok(Priority::High),
+            _ => Err(TodoError::InvalidPriority(s.to_string())),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    Priority,
+    #[default]
+    Due,
+    Created,
+    Updated,
+}
+
+impl SortKey {
+    pub fn from_string(s: &str) -> Result<Self> {
+        match normalize(s).as_str() {
+            "priority" | "p" => Ok(SortKey::Priority),
+            "due" | "d" => Ok(SortKey::Due),
+            "created" | "c" => Ok(SortKey::Created),
+            "updated" | "u" => Ok(SortKey::Updated),
+            _ => Err(TodoError::InvalidSort(s.to_string())),
+        }
+    }
+}
+
+/// How often a todo comes back once completed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Recurrence {
+    pub fn from_string(s: &str) -> Result<Self> {
+        match normalize(s).as_str() {
+            "daily" | "day" => Ok(Recurrence::Daily),
+            "weekly" | "week" => Ok(Recurrence::Weekly),
+            "monthly" | "month" => Ok(Recurrence::Monthly),
+            _ => Err(TodoError::InvalidRecurrence(s.to_string())),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Recurrence::Daily => "daily",
+            Recurrence::Weekly => "weekly",
+            Recurrence::Monthly => "monthly",
+        }
+    }
+
+    /// The due date one period after `due`. Monthly dates past the end of a
+    /// shorter month land on its last day.
+    pub fn next_due(self, due: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Recurrence::Daily => due + Duration::days(1),
+            Recurrence::Weekly => due + Duration::weeks(1),
+            Recurrence::Monthly => due.checked_add_months(Months::new(1)).unwrap_or(due),
+        }
+    }
+}
+
+/// Criteria shared by list views and bulk commands. Unset fields match everything.
+#[derive(Debug, Default)]
+pub struct TodoFilter {
+    pub status: Option<Status>,
+    pub priority: Option<Priority>,
+    pub tag: Option<String>,
+    pub project: Option<String>,
+    /// Match archived todos instead of the live ones.
+    pub archived: bool,
+    /// Match someday/maybe todos instead of committed ones.
+    pub someday: bool,
+}
+
+impl TodoFilter {
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none() && self.priority.is_none() && self.tag.is_none() && self.project.is_none() && !self.archived && !self.someday
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        todo.archived == self.archived
+            && todo.someday == self.someday
+            && self.status.as_ref().is_none_or(|s| todo.status == *s)
+            && self.priority.as_ref().is_none_or(|p| todo.priority == *p)
+            && self.tag.as_ref().is_none_or(|t| todo.has_tag(t))
+            && self.project.as_ref().is_none_or(|p| todo.in_project(p))
+    }
+}
+
+/// One part of a todo `search` can be limited to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchField {
+    Title,
+    Description,
+    Tags,
+    Subtasks,
+}
+
+impl SearchField {
+    const ALL: [SearchField; 4] = [SearchField::Title, SearchField::Description, SearchField::Tags, SearchField::Subtasks];
+
+    pub fn from_string(s: &str) -> Result<Self> {
+        match normalize(s).as_str() {
+            "title" | "t" => Ok(SearchField::Title),
+            "description" | "desc" | "d" => Ok(SearchField::Description),
+            "tags" | "tag" => Ok(SearchField::Tags),
+            "subtasks" | "subtask" => Ok(SearchField::Subtasks),
+            _ => Err(TodoError::InvalidSearchField(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Pattern {
+    /// Lowercased, for a case-insensitive substring match.
+    Text(String),
+    Regex(Regex),
+}
+
+/// A compiled `todo search` query. Both plain and regex queries ignore case
+/// (a regex can turn that off with `(?-i)`), and without a field every field
+/// is searched.
+#[derive(Debug)]
+pub struct Search {
+    pattern: Pattern,
+    field: Option<SearchField>,
+}
+
+impl Search {
+    pub fn new(query: &str, field: Option<SearchField>, regex: bool) -> Result<Self> {
+        let pattern = if regex {
+            let compiled = RegexBuilder::new(query)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| TodoError::InvalidPattern(e.to_string()))?;
+            Pattern::Regex(compiled)
+        } else {
+            Pattern::Text(query.to_lowercase())
+        };
+        Ok(Self { pattern, field })
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        match self.field {
+            Some(field) => self.matches_field(todo, field),
+            None => SearchField::ALL.iter().any(|&field| self.matches_field(todo, field)),
+        }
+    }
+
+    fn matches_field(&self, todo: &Todo, field: SearchField) -> bool {
+        match field {
+            SearchField::Title => self.matches_text(&todo.title),
+            SearchField::Description => todo.description.as_deref().is_some_and(|d| self.matches_text(d)),
+            // Tags are stored without `#`, so `#work` finds them too
+            SearchField::Tags => todo.tags.iter().any(|tag| match &self.pattern {
+                Pattern::Text(query) => tag.contains(query.trim_start_matches('#')),
+                Pattern::Regex(regex) => regex.is_match(tag),
+            }),
+            SearchField::Subtasks => todo.subtasks.iter().any(|s| self.matches_text(&s.title)),
+        }
+    }
+
+    fn matches_text(&self, text: &str) -> bool {
+        match &self.pattern {
+            Pattern::Text(query) => text.to_lowercase().contains(query.as_str()),
+            Pattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Subtask {
+    pub title: String,
+    pub done: bool,
+}
+
+/// An intermediate deadline on the way to the todo's due date.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Milestone {
+    pub label: String,
+    /// Local wall-clock time, stored like `Todo::due_date`.
+    pub date: NaiveDateTime,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Todo {
     pub id: String,
     pub title: String,
@@ -51,6 +370,101 @@ pub struct Todo {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub user_id: String,
+    /// Who originally created the todo; `user_id` is the current owner.
+    #[serde(default)]
+    pub created_by: String,
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    /// Kept in date order.
+    #[serde(default)]
+    pub milestones: Vec<Milestone>,
+    /// Ids of other users who can see and complete this todo.
+    #[serde(default)]
+    pub shared_with: Vec<String>,
+    /// Caller-supplied stable key used by `add --upsert`.
+    #[serde(default)]
+    pub external_key: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Minutes before the due date to remind at, set with `--remind-before`.
+    /// When present these replace the global reminder thresholds.
+    #[serde(default)]
+    pub reminder_offsets: Vec<i64>,
+    /// Set while the todo is parked on someone else, with `todo wait`.
+    #[serde(default)]
+    pub waiting: Option<Waiting>,
+    /// Set by `todo ack` to hush the todo's reminder.
+    #[serde(default)]
+    pub acknowledged: Option<Acknowledgement>,
+    /// Reminders for this todo stay quiet until then.
+    #[serde(default)]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// Expected effort, set with `add --estimate`.
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>,
+    /// When the todo was last completed; cleared by `reopen`.
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// How it went, from `complete --note`; cleared by `reopen`.
+    #[serde(default)]
+    pub completion_note: Option<String>,
+    /// Set with `add --every`. Completing the todo moves this on to the next
+    /// occurrence.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Out of list views but kept for history, like completed recurrences
+    /// with `archive_completed_recurrences`.
+    #[serde(default)]
+    pub archived: bool,
+    /// Parked as someday/maybe: left out of lists, plans and reminders until
+    /// toggled back with `todo someday`.
+    #[serde(default)]
+    pub someday: bool,
+    /// IANA zone `due_date` is wall-clock time in, set with `add --tz`, for
+    /// deadlines like "9am in New York". Local time when unset.
+    #[serde(default)]
+    pub due_tz: Option<String>,
+    /// Who has this on their focus list, by user id, and the day they put
+    /// it there with `todo focus <id>`. Each user has their own list, and
+    /// entries from earlier days are dropped at startup.
+    #[serde(default)]
+    pub focused_on: BTreeMap<String, chrono::NaiveDate>,
+}
+
+/// A pending todo that's delegated: who or what it waits on, and since when.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Waiting {
+    pub on: Option<String>,
+    pub since: DateTime<Utc>,
+}
+
+/// What keeps a todo out of actionable views.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Blocker {
+    Completed,
+    Waiting,
+    Someday,
+    Snoozed,
+}
+
+impl Blocker {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Blocker::Completed => "completed",
+            Blocker::Waiting => "waiting",
+            Blocker::Someday => "someday",
+            Blocker::Snoozed => "snoozed",
+        }
+    }
+}
+
+/// A dismissed reminder: when, and how urgent it was (`ReminderKind::urgency`).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Acknowledgement {
+    pub at: DateTime<Utc>,
+    pub urgency: u8,
 }
 
 impl Todo {
@@ -71,74 +485,1353 @@ impl Todo {
             due_date,
             created_at: now,
             updated_at: now,
+            created_by: user_id.clone(),
             user_id,
+            subtasks: Vec::new(),
+            milestones: Vec::new(),
+            shared_with: Vec::new(),
+            external_key: None,
+            tags: Vec::new(),
+            project: None,
+            reminder_offsets: Vec::new(),
+            waiting: None,
+            acknowledged: None,
+            snoozed_until: None,
+            estimate_minutes: None,
+            completed_at: None,
+            completion_note: None,
+            recurrence: None,
+            archived: false,
+            someday: false,
+            due_tz: None,
+            focused_on: BTreeMap::new(),
+        }
+    }
+
+    /// When the todo is due, reading `due_date` in `due_tz` or else local time.
+    pub fn due_at(&self) -> Option<DateTime<Local>> {
+        self.due_date.map(|due| dates::due_instant(due, self.due_tz.as_deref()))
+    }
+
+    /// The local day the todo is due on: the stored date, unless `due_tz`
+    /// puts the deadline on another day here.
+    pub fn due_day(&self) -> Option<chrono::NaiveDate> {
+        match self.due_tz {
+            Some(_) => self.due_at().map(|due| due.date_naive()),
+            None => self.due_date.map(|due| due.date()),
+        }
+    }
+
+    /// On `user_id`'s focus list for `today`.
+    pub fn in_focus(&self, user_id: &str, today: chrono::NaiveDate) -> bool {
+        self.focused_on.get(user_id) == Some(&today)
+    }
+
+    /// Pending with a due date before `now`.
+    pub fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        self.status == Status::Pending && self.due_at().is_some_and(|due| due < now)
+    }
+
+    /// Why the todo can't be worked on right now, or `None` if it can. The
+    /// one definition of "actionable" behind `list --actionable` and the
+    /// dashboard's actionable view.
+    pub fn blocker(&self, now: DateTime<Local>) -> Option<Blocker> {
+        match self.status {
+            Status::Completed => Some(Blocker::Completed),
+            Status::Pending if self.waiting.is_some() => Some(Blocker::Waiting),
+            Status::Pending if self.someday => Some(Blocker::Someday),
+            Status::Pending if self.is_snoozed(now) => Some(Blocker::Snoozed),
+            Status::Pending => None,
         }
     }
+
+    /// When a completed todo was finished. Todos completed before
+    /// `completed_at` was recorded fall back to their last update.
+    pub fn completed_time(&self) -> Option<DateTime<Utc>> {
+        match self.status {
+            Status::Completed => Some(self.completed_at.unwrap_or(self.updated_at)),
+            Status::Pending => None,
+        }
+    }
+
+    pub fn is_actionable(&self, now: DateTime<Local>) -> bool {
+        self.blocker(now).is_none()
+    }
+
+    pub fn is_snoozed(&self, now: DateTime<Local>) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    /// Project names compare case-insensitively, like tags.
+    pub fn in_project(&self, project: &str) -> bool {
+        self.project.as_deref().is_some_and(|p| p.eq_ignore_ascii_case(project.trim()))
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        self.tags.contains(&tag)
+    }
+
+    pub fn is_visible_to(&self, user_id: &str) -> bool {
+        self.user_id == user_id || self.shared_with.iter().any(|id| id == user_id)
+    }
+
+    /// The earliest milestone not yet done.
+    pub fn next_milestone(&self) -> Option<&Milestone> {
+        self.milestones.iter().find(|m| !m.done)
+    }
+
+    /// The next instance of a recurring todo: a fresh copy due one period
+    /// later, with the same tags, priority and sharing, and its subtasks
+    /// unticked. `None` unless the todo recurs and has a due date.
+    pub fn next_occurrence(&self) -> Option<Todo> {
+        let recurrence = self.recurrence?;
+        let due = recurrence.next_due(self.due_date?);
+        let mut next = Todo::new(self.title.clone(), self.description.clone(), self.priority.clone(), Some(due), self.user_id.clone());
+        next.created_by = self.created_by.clone();
+        next.subtasks = self.subtasks.iter()
+            .map(|s| Subtask { title: s.title.clone(), done: false })
+            .collect();
+        next.shared_with = self.shared_with.clone();
+        next.tags = self.tags.clone();
+        next.project = self.project.clone();
+        next.reminder_offsets = self.reminder_offsets.clone();
+        next.estimate_minutes = self.estimate_minutes;
+        next.due_tz = self.due_tz.clone();
+        next.recurrence = Some(recurrence);
+        Some(next)
+    }
+
+    /// Returns `(done, total)` subtask counts, or `None` when there are no subtasks.
+    pub fn subtask_progress(&self) -> Option<(usize, usize)> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        let done = self.subtasks.iter().filter(|s| s.done).count();
+        Some((done, self.subtasks.len()))
+    }
 }
 
 pub struct TodoManager {
     storage: Storage,
     todos: HashMap<String, Todo>,
+    /// `unique_prefix_len` of the stored ids, cleared when todos are added.
+    id_prefix: Cell<Option<usize>>,
+    /// `archive_completed_recurrences` from the config.
+    archive_recurrences: bool,
+    /// Told about adds, completions and deletes once they are saved.
+    webhook: Option<Webhook>,
+    /// `max_todos_per_user` from the config.
+    max_open: Option<usize>,
+    /// `skip_weekends` from the config, for the next occurrence of repeating todos.
+    skip_weekends: bool,
+    /// Writes held back while `with_batched_writes` runs.
+    batch: Option<Batch>,
+    /// `auto_save` from the config. When off, changes wait in `unsaved`
+    /// until `flush`.
+    auto_save: bool,
+    unsaved: Batch,
+}
+
+/// What a `with_batched_writes` closure, or a session without auto-save,
+/// has changed so far.
+#[derive(Default)]
+struct Batch {
+    todos: bool,
+    markdown: bool,
+    events: Vec<(Event, Vec<Todo>)>,
+}
+
+impl Batch {
+    fn merge(&mut self, other: Batch) {
+        self.todos |= other.todos;
+        self.markdown |= other.markdown;
+        self.events.extend(other.events);
+    }
 }
 
 impl TodoManager {
     pub fn new(storage: &Storage) -> Result<Self> {
-        let todos = storage.load_todos()?;
+        let mut todos = storage.load_todos()?;
+
+        // Todos saved before `created_by` existed were created by their owner
+        let mut migrated = false;
+        for todo in todos.values_mut().filter(|t| t.created_by.is_empty()) {
+            todo.created_by = todo.user_id.clone();
+            migrated = true;
+        }
+        if migrated {
+            storage.save_todos(&todos)?;
+        }
+
         Ok(Self {
             storage: storage.clone(),
             todos,
+            id_prefix: Cell::new(None),
+            archive_recurrences: false,
+            webhook: None,
+            max_open: None,
+            skip_weekends: false,
+            batch: None,
+            auto_save: true,
+            unsaved: Batch::default(),
         })
     }
 
+    /// Archive completed instances of recurring todos instead of leaving
+    /// them among the live ones.
+    pub fn set_archive_recurrences(&mut self, archive: bool) {
+        self.archive_recurrences = archive;
+    }
+
+    /// Keep the next occurrence of repeating todos off weekends.
+    pub fn set_skip_weekends(&mut self, skip: bool) {
+        self.skip_weekends = skip;
+    }
+
+    /// Save after every change, or only on `flush`. Turning it back on
+    /// doesn't write what is already unsaved.
+    pub fn set_auto_save(&mut self, auto_save: bool) {
+        self.auto_save = auto_save;
+    }
+
+    /// Whether there are changes `flush` would write.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.unsaved.todos || self.unsaved.markdown
+    }
+
+    /// Writes everything held back because auto-save is off, then sends
+    /// the webhooks for it.
+    pub async fn flush(&mut self) -> Result<()> {
+        let unsaved = std::mem::take(&mut self.unsaved);
+        self.write(unsaved).await
+    }
+
+    pub fn set_webhook(&mut self, webhook: Option<Webhook>) {
+        self.webhook = webhook;
+    }
+
+    /// Most open todos `add_todo` and `add_todos` will let one user reach;
+    /// `None` for no limit.
+    pub fn set_max_open(&mut self, limit: Option<usize>) {
+        self.max_open = limit;
+    }
+
+    /// Every stored todo, whoever owns it.
+    pub fn total_count(&self) -> usize {
+        self.todos.len()
+    }
+
+    /// Todos counted against `max_todos_per_user`: pending, not archived,
+    /// and owned (not just shared with) by `user_id`.
+    pub fn open_count(&self, user_id: &str) -> usize {
+        self.todos.values().filter(|t| t.user_id == user_id && counts_as_open(t)).count()
+    }
+
+    /// Fails if adding `todos` would take any owner past the limit.
+    fn check_limit(&self, todos: &[Todo]) -> Result<()> {
+        let Some(limit) = self.max_open else {
+            return Ok(());
+        };
+        let mut adding: HashMap<&str, usize> = HashMap::new();
+        for todo in todos.iter().filter(|t| counts_as_open(t)) {
+            *adding.entry(&todo.user_id).or_default() += 1;
+        }
+        if adding.iter().any(|(user_id, count)| self.open_count(user_id) + count > limit) {
+            return Err(TodoError::TooManyTodos(limit));
+        }
+        Ok(())
+    }
+
+    /// Pending todos the user can see whose titles are at least `threshold`
+    /// similar to `title` (0.0 to 1.0), most similar first.
+    pub fn find_similar(&self, user_id: &str, title: &str, threshold: f64) -> Vec<(f64, &Todo)> {
+        let wanted = comparable_title(title);
+        let mut similar: Vec<(f64, &Todo)> = self.todos.values()
+            .filter(|t| t.is_visible_to(user_id) && t.status == Status::Pending && !t.archived)
+            .map(|t| (strsim::normalized_levenshtein(&wanted, &comparable_title(&t.title)), t))
+            .filter(|(score, _)| *score >= threshold)
+            .collect();
+        similar.sort_by(|a, b| b.0.partial_cmp(&a.0)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.1.created_at.cmp(&b.1.created_at)));
+        similar
+    }
+
+    /// Splits `todos` into ones to add and duplicates: todos whose title
+    /// matches, ignoring case and punctuation, one their owner already has
+    /// (archived ones aside) or an earlier one in the batch.
+    pub fn split_duplicates(&self, todos: Vec<Todo>) -> (Vec<Todo>, Vec<Todo>) {
+        let mut seen: HashSet<(String, String)> = self.todos.values()
+            .filter(|t| !t.archived)
+            .map(|t| (t.user_id.clone(), comparable_title(&t.title)))
+            .collect();
+        todos.into_iter()
+            .partition(|t| seen.insert((t.user_id.clone(), comparable_title(&t.title))))
+    }
+
+    /// Runs after the change is saved, so a slow or failing endpoint can't
+    /// lose it. Inside a batch it waits until the batch is written.
+    async fn notify(&mut self, event: Event, todos: &[Todo]) {
+        if let Some(batch) = self.pending() {
+            if !todos.is_empty() {
+                batch.events.push((event, todos.to_vec()));
+            }
+            return;
+        }
+        if let Some(webhook) = &self.webhook {
+            if !todos.is_empty() {
+                webhook.send(event, todos).await;
+            }
+        }
+    }
+
+    /// Where writes are being held back: the running batch, or the unsaved
+    /// changes when auto-save is off. `None` when they happen right away.
+    fn pending(&mut self) -> Option<&mut Batch> {
+        match &mut self.batch {
+            Some(batch) => Some(batch),
+            None if !self.auto_save => Some(&mut self.unsaved),
+            None => None,
+        }
+    }
+
+    /// Writes the todos file, or inside a batch marks it for writing.
+    fn save(&mut self) -> Result<()> {
+        match self.pending() {
+            Some(pending) => pending.todos = true,
+            None => self.storage.save_todos(&self.todos)?,
+        }
+        Ok(())
+    }
+
+    /// `save`, then brings `todos.md` up to date with `update`. Inside a
+    /// batch the markdown is instead regenerated once at the end.
+    fn save_with_markdown(&mut self, update: impl FnOnce(&Storage) -> anyhow::Result<()>) -> Result<()> {
+        self.save()?;
+        match self.pending() {
+            Some(pending) => pending.markdown = true,
+            None => update(&self.storage)?,
+        }
+        Ok(())
+    }
+
+    /// Carries out held-back writes: the todos file, the markdown, then the
+    /// webhooks.
+    async fn write(&mut self, changes: Batch) -> Result<()> {
+        if changes.todos {
+            self.storage.save_todos(&self.todos)?;
+        }
+        if changes.markdown {
+            self.storage.regenerate_markdown()?;
+        }
+        if let Some(webhook) = &self.webhook {
+            for (event, todos) in changes.events {
+                webhook.send(event, &todos).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `f` with every disk write deferred until it returns: however
+    /// many changes it makes, the todos file is saved and the markdown
+    /// regenerated at most once, and webhooks fire after that. Whatever `f`
+    /// changed before failing is still saved, as it would have been without
+    /// the batch. Nested calls join the outer batch. With auto-save off the
+    /// writes join the unsaved changes instead. The closure returns a boxed
+    /// future: `|m| Box::pin(async move { ... })`.
+    pub async fn with_batched_writes<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: for<'m> FnOnce(&'m mut TodoManager) -> Pin<Box<dyn Future<Output = Result<T>> + 'm>>,
+    {
+        if self.batch.is_some() {
+            return f(self).await;
+        }
+        self.batch = Some(Batch::default());
+        let result = f(self).await;
+        let batch = self.batch.take().unwrap_or_default();
+        if self.auto_save {
+            self.write(batch).await?;
+        } else {
+            self.unsaved.merge(batch);
+        }
+        result
+    }
+
+    /// How many id characters to show: at least `min`, and more when that's
+    /// what it takes to tell every stored todo apart, like git's short hashes.
+    pub fn id_display_length(&self, min: usize) -> usize {
+        let needed = self.id_prefix.get().unwrap_or_else(|| {
+            let needed = unique_prefix_len(self.todos.keys().map(String::as_str), 0);
+            self.id_prefix.set(Some(needed));
+            needed
+        });
+        needed.max(min)
+    }
+
     pub async fn add_todo(&mut self, todo: Todo) -> Result<()> {
+        self.check_limit(std::slice::from_ref(&todo))?;
+        self.id_prefix.set(None);
         self.todos.insert(todo.id.clone(), todo.clone());
-        self.storage.save_todos(&self.todos)?;
-        self.storage.append_to_markdown(&todo)?;
+        self.save_with_markdown(|storage| storage.append_to_markdown(&todo))?;
+        self.notify(Event::Added, &[todo]).await;
         Ok(())
     }
 
+    /// Adds many todos with a single save, for imports.
+    pub async fn add_todos(&mut self, todos: Vec<Todo>) -> Result<usize> {
+        self.check_limit(&todos)?;
+        let count = todos.len();
+        self.id_prefix.set(None);
+        for todo in &todos {
+            self.todos.insert(todo.id.clone(), todo.clone());
+        }
+        self.save_with_markdown(Storage::regenerate_markdown)?;
+        self.notify(Event::Added, &todos).await;
+        Ok(count)
+    }
+
+    /// Completed todos per owner since `since`, across every user. Only counts
+    /// leave this method, never todo contents.
+    pub fn completed_counts_since(&self, since: DateTime<Utc>) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for todo in self.todos.values() {
+            if todo.completed_time().is_some_and(|at| at >= since) {
+                *counts.entry(todo.user_id.clone()).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    /// Records work that was never tracked: the todo is stored as completed
+    /// now, and the markdown is rebuilt so it lands in the completed section.
+    pub async fn log_completed(&mut self, mut todo: Todo) -> Result<Todo> {
+        let now = Utc::now();
+        todo.status = Status::Completed;
+        todo.created_at = now;
+        todo.updated_at = now;
+        todo.completed_at = Some(now);
+
+        self.id_prefix.set(None);
+        self.todos.insert(todo.id.clone(), todo.clone());
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&todo))?;
+        self.notify(Event::Completed, std::slice::from_ref(&todo)).await;
+        Ok(todo)
+    }
+
     pub async fn get_user_todos(&self, user_id: &str) -> Result<Vec<Todo>> {
         Ok(self.todos.values()
-            .filter(|todo| todo.user_id == user_id)
+            .filter(|todo| todo.is_visible_to(user_id))
             .cloned()
             .collect())
     }
 
+    pub fn find_by_key(&self, user_id: &str, key: &str) -> Option<&Todo> {
+        self.todos.values()
+            .find(|t| t.user_id == user_id && t.external_key.as_deref() == Some(key))
+    }
+
+    /// Creates `todo`, or if its owner already has a todo with the same
+    /// `external_key`, updates that one's details in place. Returns the stored
+    /// todo and whether it was newly created.
+    pub async fn upsert_by_key(&mut self, todo: Todo) -> Result<(Todo, bool)> {
+        let key = todo.external_key.clone()
+            .ok_or(TodoError::MissingKey)?;
+
+        let existing_id = self.find_by_key(&todo.user_id, &key).map(|t| t.id.clone());
+        match existing_id {
+            Some(id) => {
+                let updated_todo = {
+                    let existing = self.todos.get_mut(&id)
+                        .ok_or(TodoError::NotFound)?;
+                    existing.title = todo.title;
+                    existing.description = todo.description;
+                    existing.priority = todo.priority;
+                    existing.due_date = todo.due_date;
+                    existing.due_tz = todo.due_tz;
+                    existing.project = todo.project;
+                    existing.reminder_offsets = todo.reminder_offsets;
+                    existing.estimate_minutes = todo.estimate_minutes;
+                    existing.updated_at = Utc::now();
+                    existing.clone()
+                };
+                let owner = updated_todo.user_id.clone();
+                self.update_todo(updated_todo.clone(), &owner).await?;
+                Ok((updated_todo, false))
+            },
+            None => {
+                self.add_todo(todo.clone()).await?;
+                Ok((todo, true))
+            },
+        }
+    }
+
+    /// The default `list` view: the user's live todos in `project`, ordered
+    /// by `sort`. List positions count against exactly this.
+    pub fn listing(&self, user_id: &str, project: Option<&str>, sort: SortKey) -> Vec<&Todo> {
+        let filter = TodoFilter { project: project.map(str::to_string), ..Default::default() };
+        let mut listing: Vec<&Todo> = self.todos.values()
+            .filter(|t| t.is_visible_to(user_id) && filter.matches(t))
+            .collect();
+        sort_todos(&mut listing, sort);
+        listing
+    }
+
+    /// Turns what the user typed into a todo id. Besides a full id this
+    /// accepts a unique id prefix, `last` for the newest todo, and a position
+    /// in the default `list` view (`listing`).
+    /// Positions are only meaningful against that view; they are not ids and
+    /// shift as todos are added or completed.
+    pub fn resolve_reference(&self, reference: &str, user_id: &str, project: Option<&str>, sort: SortKey) -> Result<String> {
+        let reference = reference.trim();
+        if self.todos.get(reference).is_some_and(|t| t.is_visible_to(user_id)) {
+            return Ok(reference.to_string());
+        }
+
+        let visible: Vec<&Todo> = self.todos.values()
+            .filter(|t| t.is_visible_to(user_id))
+            .collect();
+
+        if reference.eq_ignore_ascii_case("last") {
+            return visible.iter()
+                .max_by_key(|t| t.created_at)
+                .map(|t| t.id.clone())
+                .ok_or(TodoError::NotFound);
+        }
+
+        // Short numbers are positions; eight or more digits are read as an id prefix
+        if reference.len() < 8 {
+            if let Ok(position) = reference.parse::<usize>() {
+                return position.checked_sub(1)
+                    .and_then(|i| self.listing(user_id, project, sort).get(i).copied())
+                    .map(|t| t.id.clone())
+                    .ok_or(TodoError::NoSuchPosition(position));
+            }
+        }
+
+        let mut matches = visible.iter().filter(|t| !reference.is_empty() && t.id.starts_with(reference));
+        match (matches.next(), matches.next()) {
+            (Some(todo), None) => Ok(todo.id.clone()),
+            (Some(_), Some(_)) => Err(TodoError::AmbiguousReference(reference.to_string())),
+            (None, _) => Err(TodoError::NotFound),
+        }
+    }
+
     pub async fn get_todo(&self, todo_id: &str) -> Result<Todo> {
         self.todos.get(todo_id)
             .cloned()
-            .ok_or_else(|| anyhow!("Todo not found"))
+            .ok_or(TodoError::NotFound)
     }
 
-    pub async fn complete_todo(&mut self, todo_id: &str) -> Result<()> {
+    /// Marks a todo completed, with an optional note on the outcome. Returns
+    /// `false`, without touching anything on disk, if it already was.
+    pub async fn complete_todo(&mut self, todo_id: &str, user_id: &str, note: Option<String>) -> Result<bool> {
         // Scope the mutable borrow so it ends before we use `todo` again
+        let (updated_todo, next) = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            if todo.status == Status::Completed {
+                return Ok(false);
+            }
+            todo.waiting = None;
+            todo.completion_note = note;
+            let next = finish(todo, Utc::now(), self.archive_recurrences, self.skip_weekends);
+            (todo.clone(), next) // Clone so borrow ends here
+        };
+        if let Some(next) = next.clone() {
+            self.id_prefix.set(None);
+            self.todos.insert(next.id.clone(), next);
+        }
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        self.notify(Event::Completed, &[updated_todo]).await;
+        if let Some(next) = next {
+            self.notify(Event::Added, &[next]).await;
+        }
+        Ok(true)
+    }
+
+    /// Moves a completed todo back to pending.
+    pub async fn reopen_todo(&mut self, todo_id: &str, user_id: &str) -> Result<()> {
         let updated_todo = {
             let todo = self.todos.get_mut(todo_id)
-                .ok_or_else(|| anyhow!("Todo not found"))?;
-            todo.status = Status::Completed;
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            if todo.status != Status::Completed {
+                return Err(TodoError::NotCompleted);
+            }
+            todo.status = Status::Pending;
+            todo.completed_at = None;
+            todo.completion_note = None;
+            todo.archived = false;
             todo.updated_at = Utc::now();
-            todo.clone() // Clone so borrow ends here
+            todo.clone()
         };
 
-        self.storage.save_todos(&self.todos)?;
-        self.storage.update_markdown_todo(&updated_todo)?;
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
         Ok(())
     }
 
-    pub async fn update_todo(&mut self, updated_todo: Todo) -> Result<()> {
+    /// Moves a pending todo's due date later as `deferral` says, counting
+    /// from `today` when it has none, then past the weekend when
+    /// `business_days` is set.
+    pub async fn defer_todo(&mut self, todo_id: &str, user_id: &str, deferral: Deferral, today: chrono::NaiveDate, business_days: bool) -> Result<Todo> {
+        let updated_todo = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            if todo.status == Status::Completed {
+                return Err(TodoError::AlreadyCompleted);
+            }
+            let mut due = deferral.apply(todo.due_date, today);
+            if business_days {
+                due = dates::skip_weekend(due);
+            }
+            if todo.due_date.is_some_and(|current| due < current) {
+                return Err(TodoError::DeferBackwards);
+            }
+            todo.due_date = Some(due);
+            todo.updated_at = Utc::now();
+            todo.clone()
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok(updated_todo)
+    }
+
+    /// `defer_todo` for each listed todo, saved once. Todos it would move
+    /// earlier are left alone. Returns the ones that moved.
+    pub async fn bulk_defer(&mut self, todo_ids: Vec<String>, user_id: &str, deferral: Deferral, today: chrono::NaiveDate, business_days: bool) -> Result<Vec<Todo>> {
+        let user_id = user_id.to_string();
+        self.with_batched_writes(|manager| Box::pin(async move {
+            let mut moved = Vec::new();
+            for todo_id in todo_ids {
+                match manager.defer_todo(&todo_id, &user_id, deferral, today, business_days).await {
+                    Ok(todo) => moved.push(todo),
+                    Err(TodoError::DeferBackwards) => {},
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(moved)
+        })).await
+    }
+
+    /// Parks a todo as waiting on `on` (if given), or with `None` takes it
+    /// off hold again.
+    pub async fn set_waiting(&mut self, todo_id: &str, user_id: &str, waiting: Option<Option<String>>) -> Result<Todo> {
+        let updated_todo = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            if todo.status == Status::Completed {
+                return Err(TodoError::AlreadyCompleted);
+            }
+            todo.waiting = waiting.map(|on| Waiting { on, since: Utc::now() });
+            todo.updated_at = Utc::now();
+            todo.clone()
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok(updated_todo)
+    }
+
+    /// Moves a pending todo onto the someday/maybe list, or back off it.
+    pub async fn toggle_someday(&mut self, todo_id: &str, user_id: &str) -> Result<Todo> {
+        let updated_todo = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            if todo.status == Status::Completed {
+                return Err(TodoError::AlreadyCompleted);
+            }
+            todo.someday = !todo.someday;
+            todo.updated_at = Utc::now();
+            todo.clone()
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok(updated_todo)
+    }
+
+    /// Puts a todo on the user's focus list for `today` or takes it off.
+    /// Completed todos can stay on it but not be added.
+    pub async fn set_focus(&mut self, todo_id: &str, user_id: &str, focus: bool, today: chrono::NaiveDate) -> Result<Todo> {
+        let updated_todo = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            if focus {
+                if todo.status == Status::Completed {
+                    return Err(TodoError::AlreadyCompleted);
+                }
+                todo.focused_on.insert(user_id.to_string(), today);
+            } else {
+                todo.focused_on.remove(user_id);
+            }
+            todo.clone()
+        };
+
+        self.save()?;
+        Ok(updated_todo)
+    }
+
+    /// Empties the user's focus list, returning how many todos were on it.
+    /// Other users' lists, even on shared todos, are left alone.
+    pub async fn clear_focus(&mut self, user_id: &str) -> Result<usize> {
+        let mut cleared = 0;
+        for todo in self.todos.values_mut() {
+            if todo.focused_on.remove(user_id).is_some() {
+                cleared += 1;
+            }
+        }
+        if cleared > 0 {
+            self.save()?;
+        }
+        Ok(cleared)
+    }
+
+    /// Drops focus entries made on a day before `today`, so every list
+    /// starts the day empty. Run at startup; saves only if something went.
+    pub fn roll_over_focus(&mut self, today: chrono::NaiveDate) -> Result<()> {
+        let mut changed = false;
+        for todo in self.todos.values_mut() {
+            let before = todo.focused_on.len();
+            todo.focused_on.retain(|_, day| *day == today);
+            changed |= todo.focused_on.len() != before;
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Changes just the priority, returning the todo and its old priority.
+    pub async fn set_priority(&mut self, todo_id: &str, user_id: &str, priority: Priority) -> Result<(Todo, Priority)> {
+        let (updated_todo, previous) = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            let previous = std::mem::replace(&mut todo.priority, priority);
+            todo.updated_at = Utc::now();
+            (todo.clone(), previous)
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok((updated_todo, previous))
+    }
+
+    /// Replaces a stored todo wholesale, as `edit` does. Fails as not found
+    /// unless `user_id` can see the todo being replaced.
+    pub async fn update_todo(&mut self, updated_todo: Todo, user_id: &str) -> Result<()> {
+        self.todos.get(&updated_todo.id)
+            .filter(|t| t.is_visible_to(user_id))
+            .ok_or(TodoError::NotFound)?;
+        self.id_prefix.set(None);
         self.todos.insert(updated_todo.id.clone(), updated_todo.clone());
-        self.storage.save_todos(&self.todos)?;
-        self.storage.update_markdown_todo(&updated_todo)?;
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
         Ok(())
     }
 
-    pub async fn delete_todo(&mut self, todo_id: &str) -> Result<()> {
+    /// Records reminder acknowledgements, saving once. Leaves `updated_at`
+    /// alone, since a real edit is what should bring a reminder back.
+    pub async fn acknowledge(&mut self, acks: Vec<(String, Acknowledgement)>) -> Result<usize> {
+        let mut count = 0;
+        for (id, ack) in acks {
+            if let Some(todo) = self.todos.get_mut(&id) {
+                todo.acknowledged = Some(ack);
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.save()?;
+        }
+        Ok(count)
+    }
+
+    /// Snoozes (or, with `None`, unsnoozes) every pending todo the user owns,
+    /// saving once. Like acknowledgements this leaves `updated_at` alone.
+    /// Returns how many todos changed.
+    pub async fn snooze_all(&mut self, user_id: &str, until: Option<DateTime<Utc>>) -> Result<usize> {
+        if until.is_some_and(|until| until <= Utc::now()) {
+            return Err(TodoError::SnoozeInPast);
+        }
+        let mut count = 0;
+        for todo in self.todos.values_mut() {
+            if todo.user_id != user_id || todo.status != Status::Pending {
+                continue;
+            }
+            if until.is_none() && todo.snoozed_until.is_none() {
+                continue;
+            }
+            todo.snoozed_until = until;
+            count += 1;
+        }
+        if count > 0 {
+            self.save()?;
+        }
+        Ok(count)
+    }
+
+    /// Todos owned by someone not in `user_ids`. Ones already archived are
+    /// only included with `include_archived`.
+    pub fn orphans(&self, user_ids: &HashSet<String>, include_archived: bool) -> Vec<&Todo> {
+        self.todos.values()
+            .filter(|t| !user_ids.contains(&t.user_id) && (include_archived || !t.archived))
+            .collect()
+    }
+
+    /// Deletes orphaned todos, or with `archive` archives the ones that
+    /// aren't yet, and drops ids not in `user_ids` from every sharing list,
+    /// saving once. Returns how many orphans were handled and how many
+    /// shares dropped.
+    pub async fn prune_orphans(&mut self, user_ids: &HashSet<String>, archive: bool) -> Result<(usize, usize)> {
+        let orphan_ids: Vec<String> = self.orphans(user_ids, !archive).iter().map(|t| t.id.clone()).collect();
+        let mut removed = Vec::new();
+        for id in &orphan_ids {
+            if archive {
+                if let Some(todo) = self.todos.get_mut(id) {
+                    todo.archived = true;
+                }
+            } else {
+                removed.extend(self.todos.remove(id));
+            }
+        }
+
+        let mut shares = 0;
+        for todo in self.todos.values_mut() {
+            let before = todo.shared_with.len();
+            todo.shared_with.retain(|id| user_ids.contains(id));
+            shares += before - todo.shared_with.len();
+        }
+
+        if !orphan_ids.is_empty() || shares > 0 {
+            self.id_prefix.set(None);
+            self.save_with_markdown(Storage::regenerate_markdown)?;
+        }
+        self.notify(Event::Deleted, &removed).await;
+        Ok((orphan_ids.len(), shares))
+    }
+
+    pub async fn add_subtask(&mut self, todo_id: &str, user_id: &str, title: String) -> Result<()> {
+        let updated_todo = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            todo.subtasks.push(Subtask { title, done: false });
+            todo.updated_at = Utc::now();
+            todo.clone()
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok(())
+    }
+
+    /// Adds a milestone, keeping the list in date order, and returns its
+    /// 1-based position.
+    pub async fn add_milestone(&mut self, todo_id: &str, user_id: &str, label: String, date: NaiveDateTime) -> Result<usize> {
+        let (updated_todo, position) = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            let index = todo.milestones.partition_point(|m| m.date <= date);
+            todo.milestones.insert(index, Milestone { label, date, done: false });
+            todo.updated_at = Utc::now();
+            (todo.clone(), index + 1)
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok(position)
+    }
+
+    /// Flips the milestone at `index` (0-based) and returns a copy of it.
+    pub async fn toggle_milestone(&mut self, todo_id: &str, user_id: &str, index: usize) -> Result<Milestone> {
+        let (updated_todo, milestone) = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            let milestone = todo.milestones.get_mut(index)
+                .ok_or(TodoError::MilestoneNotFound(index + 1))?;
+            milestone.done = !milestone.done;
+            let milestone = milestone.clone();
+            todo.updated_at = Utc::now();
+            (todo.clone(), milestone)
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok(milestone)
+    }
+
+    /// Flips the subtask at `index` (0-based) and returns its new done state.
+    pub async fn toggle_subtask(&mut self, todo_id: &str, user_id: &str, index: usize) -> Result<bool> {
+        let (updated_todo, done) = {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            let subtask = todo.subtasks.get_mut(index)
+                .ok_or(TodoError::SubtaskNotFound(index + 1))?;
+            subtask.done = !subtask.done;
+            let done = subtask.done;
+            todo.updated_at = Utc::now();
+            (todo.clone(), done)
+        };
+
+        self.save_with_markdown(|storage| storage.update_markdown_todo(&updated_todo))?;
+        Ok(done)
+    }
+
+    /// Shares a todo with another user. Only the owner may share.
+    pub async fn share_todo(&mut self, todo_id: &str, owner_id: &str, target_user_id: &str) -> Result<()> {
+        let todo = self.todos.get_mut(todo_id)
+            .filter(|t| t.is_visible_to(owner_id))
+            .ok_or(TodoError::NotFound)?;
+        if todo.user_id != owner_id {
+            return Err(TodoError::OwnerOnly("share"));
+        }
+        if target_user_id == owner_id {
+            return Err(TodoError::AlreadyOwner);
+        }
+        if !todo.shared_with.iter().any(|id| id == target_user_id) {
+            todo.shared_with.push(target_user_id.to_string());
+            todo.updated_at = Utc::now();
+        }
+
+        self.save()?;
+        Ok(())
+    }
+
+    /// Adds and removes tags across many todos the user can see with a single
+    /// save. Added tags are deduplicated and removing a missing tag is a no-op.
+    /// Nothing changes unless every todo is found. Returns how many todos
+    /// actually changed.
+    pub async fn bulk_modify_tags(&mut self, todo_ids: &[String], user_id: &str, add: &[String], remove: &[String]) -> Result<usize> {
+        if !todo_ids.iter().all(|id| self.todos.get(id).is_some_and(|t| t.is_visible_to(user_id))) {
+            return Err(TodoError::NotFound);
+        }
+        let add: Vec<String> = add.iter().map(|t| normalize_tag(t)).filter(|t| !t.is_empty()).collect();
+        let remove: Vec<String> = remove.iter().map(|t| normalize_tag(t)).collect();
+        let now = Utc::now();
+        let mut changed = 0;
+
+        for todo_id in todo_ids {
+            let todo = self.todos.get_mut(todo_id)
+                .ok_or(TodoError::NotFound)?;
+            let before = todo.tags.clone();
+
+            todo.tags.retain(|t| !remove.contains(t));
+            for tag in &add {
+                if !todo.tags.contains(tag) {
+                    todo.tags.push(tag.clone());
+                }
+            }
+
+            if todo.tags != before {
+                todo.updated_at = now;
+                changed += 1;
+            }
+        }
+
+        if changed > 0 {
+            self.save_with_markdown(Storage::regenerate_markdown)?;
+        }
+        Ok(changed)
+    }
+
+    /// Completes every listed todo the user can see that is still pending,
+    /// with a single save. Returns how many were completed.
+    pub async fn bulk_complete(&mut self, todo_ids: &[String], user_id: &str) -> Result<usize> {
+        let now = Utc::now();
+        let mut completed = Vec::new();
+        let mut next_instances = Vec::new();
+
+        for todo_id in todo_ids {
+            let todo = self.todos.get_mut(todo_id)
+                .filter(|t| t.is_visible_to(user_id))
+                .ok_or(TodoError::NotFound)?;
+            if todo.status == Status::Pending {
+                next_instances.extend(finish(todo, now, self.archive_recurrences, self.skip_weekends));
+                completed.push(todo.clone());
+            }
+        }
+        if !next_instances.is_empty() {
+            self.id_prefix.set(None);
+            self.todos.extend(next_instances.iter().map(|t| (t.id.clone(), t.clone())));
+        }
+
+        if !completed.is_empty() {
+            self.save_with_markdown(Storage::regenerate_markdown)?;
+        }
+        self.notify(Event::Completed, &completed).await;
+        self.notify(Event::Added, &next_instances).await;
+        Ok(completed.len())
+    }
+
+    /// Deletes the listed todos the user owns, with a single save. Todos only
+    /// shared with them are left alone. Returns how many were deleted.
+    pub async fn bulk_delete(&mut self, todo_ids: &[String], user_id: &str) -> Result<usize> {
+        let mut deleted = Vec::new();
+        for todo_id in todo_ids {
+            if self.todos.get(todo_id).is_some_and(|t| t.user_id == user_id) {
+                deleted.extend(self.todos.remove(todo_id));
+            }
+        }
+
+        if !deleted.is_empty() {
+            self.save_with_markdown(Storage::regenerate_markdown)?;
+        }
+        self.notify(Event::Deleted, &deleted).await;
+        Ok(deleted.len())
+    }
+
+    /// Deletes a todo. Users it is shared with may complete it but not delete it.
+    pub async fn delete_todo(&mut self, todo_id: &str, user_id: &str) -> Result<()> {
+        match self.todos.get(todo_id) {
+            Some(todo) if todo.user_id == user_id => {},
+            Some(todo) if todo.is_visible_to(user_id) => {
+                return Err(TodoError::OwnerOnly("delete"));
+            },
+            _ => return Err(TodoError::NotFound),
+        }
+
         // Remove first so mutable borrow ends early
         let removed = self.todos.remove(todo_id)
-            .ok_or_else(|| anyhow!("Todo not found"))?;
+            .ok_or(TodoError::NotFound)?;
 
-        self.storage.save_todos(&self.todos)?;
-        self.storage.remove_from_markdown(&removed)?;
+        self.save_with_markdown(|storage| storage.remove_from_markdown(&removed))?;
+        self.notify(Event::Deleted, &[removed]).await;
         Ok(())
     }
 }
+
+/// The last chance to keep changes made without auto-save, for exits that
+/// skip `flush`: an error, or Ctrl-C at a prompt. Their webhooks are not
+/// sent, since that needs the runtime.
+impl Drop for TodoManager {
+    fn drop(&mut self) {
+        let saved = (!self.unsaved.todos || self.storage.save_todos(&self.todos).is_ok())
+            && (!self.unsaved.markdown || self.storage.regenerate_markdown().is_ok());
+        if !saved {
+            eprintln!("Warning: unsaved changes to todos could not be written");
+        }
+    }
+}
+
+fn counts_as_open(todo: &Todo) -> bool {
+    todo.status == Status::Pending && !todo.archived
+}
+
+/// A title reduced to lowercase words, so case, punctuation and spacing
+/// don't count against a match.
+fn comparable_title(title: &str) -> String {
+    title.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Marks `todo` completed at `now`. A recurring todo hands its recurrence on
+/// to the returned next instance, and is archived if `archive` says so.
+/// With `skip_weekends` the next instance never falls due on a weekend.
+fn finish(todo: &mut Todo, now: DateTime<Utc>, archive: bool, skip_weekends: bool) -> Option<Todo> {
+    todo.status = Status::Completed;
+    todo.updated_at = now;
+    todo.completed_at = Some(now);
+    let mut next = todo.next_occurrence()?;
+    if skip_weekends {
+        next.due_date = next.due_date.map(dates::skip_weekend);
+    }
+    todo.recurrence = None;
+    todo.archived = archive;
+    Some(next)
+}
+
+/// A display-only nudge for Medium and Low todos due within `window`, so they
+/// stand out without their stored priority changing. High todos and overdue
+/// ones are already marked elsewhere.
+pub fn urgency_hint(todo: &Todo, now: DateTime<Local>, window: chrono::Duration) -> Option<&'static str> {
+    if todo.status != Status::Pending || todo.priority == Priority::High {
+        return None;
+    }
+    let due = todo.due_at()?;
+    (due >= now && due - now <= window).then_some("due soon")
+}
+
+/// Orders todos in place so every view lists them the same way.
+///
+/// `Priority` puts High first, `Due` is ascending with undated todos last,
+/// `Created` is oldest first and `Updated` is most recently touched first.
+/// Ties fall back to due date, then creation time.
+pub fn sort_todos(todos: &mut [&Todo], key: SortKey) {
+    todos.sort_by(|a, b| {
+        let primary = match key {
+            SortKey::Priority => b.priority.cmp(&a.priority),
+            SortKey::Due => compare_due(a, b),
+            SortKey::Created => a.created_at.cmp(&b.created_at),
+            SortKey::Updated => b.updated_at.cmp(&a.updated_at),
+        };
+        // The id breaks exact ties (todos imported together), so the order
+        // never depends on map iteration
+        primary
+            .then_with(|| compare_due(a, b))
+            .then_with(|| a.created_at.cmp(&b.created_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// `todos` under each of their tags, tags alphabetically, keeping the order
+/// within each group. A todo with several tags is in several groups;
+/// untagged todos come back separately.
+pub fn group_by_tag<'a>(todos: &[&'a Todo]) -> (BTreeMap<&'a str, Vec<&'a Todo>>, Vec<&'a Todo>) {
+    let mut groups: BTreeMap<&str, Vec<&Todo>> = BTreeMap::new();
+    let mut untagged = Vec::new();
+    for &todo in todos {
+        if todo.tags.is_empty() {
+            untagged.push(todo);
+        }
+        for tag in &todo.tags {
+            groups.entry(tag.as_str()).or_default().push(todo);
+        }
+    }
+    (groups, untagged)
+}
+
+/// Shortest prefix length, at least `min`, at which no two `ids` look the same.
+pub fn unique_prefix_len<'a>(ids: impl IntoIterator<Item = &'a str>, min: usize) -> usize {
+    let mut ids: Vec<&str> = ids.into_iter().collect();
+    ids.sort_unstable();
+    // After sorting, the longest shared prefix is always between neighbours
+    let needed = ids.windows(2)
+        .map(|pair| pair[0].bytes().zip(pair[1].bytes()).take_while(|(a, b)| a == b).count() + 1)
+        .max()
+        .unwrap_or(0);
+    needed.max(min)
+}
+
+/// The first `len` characters of an id, for display. Every shortened id goes
+/// through here.
+pub fn abbreviate(id: &str, len: usize) -> &str {
+    id.get(..len).unwrap_or(id)
+}
+
+/// Suggested working order for `todo plan`: overdue todos first, then by
+/// priority, then quick wins (smallest estimate, unestimated last), with due
+/// date and age as tie-breakers.
+pub fn plan_order(todos: &mut [&Todo], now: DateTime<Local>) {
+    todos.sort_by(|a, b| {
+        b.is_overdue(now).cmp(&a.is_overdue(now))
+            .then_with(|| b.priority.cmp(&a.priority))
+            .then_with(|| match (a.estimate_minutes, b.estimate_minutes) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+            .then_with(|| compare_due(a, b))
+            .then_with(|| a.created_at.cmp(&b.created_at))
+    });
+}
+
+/// Earliest deadline first, compared as instants so zoned due dates sort
+/// by when they actually fall.
+fn compare_due(a: &Todo, b: &Todo) -> Ordering {
+    match (a.due_at(), b.due_at()) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> TodoManager {
+        TodoManager::new(&Storage::in_memory().unwrap()).unwrap()
+    }
+
+    fn todo(title: &str, user_id: &str) -> Todo {
+        Todo::new(title.to_string(), None, Priority::Medium, None, user_id.to_string())
+    }
+
+    #[tokio::test]
+    async fn the_todo_limit_allows_exactly_max_open_todos() {
+        let mut manager = manager();
+        manager.set_max_open(Some(2));
+        manager.add_todo(todo("one", "alice")).await.unwrap();
+        manager.add_todo(todo("two", "alice")).await.unwrap();
+
+        let over = manager.add_todo(todo("three", "alice")).await;
+        assert!(matches!(over, Err(TodoError::TooManyTodos(2))));
+        assert_eq!(manager.open_count("alice"), 2);
+
+        // Other users have their own allowance
+        manager.add_todo(todo("mine", "bob")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn completed_todos_free_up_room_under_the_limit() {
+        let mut manager = manager();
+        manager.set_max_open(Some(1));
+        let first = todo("one", "alice");
+        let id = first.id.clone();
+        manager.add_todo(first).await.unwrap();
+        assert!(manager.add_todo(todo("two", "alice")).await.is_err());
+
+        manager.complete_todo(&id, "alice", None).await.unwrap();
+        manager.add_todo(todo("two", "alice")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn an_import_that_would_cross_the_limit_adds_nothing() {
+        let mut manager = manager();
+        manager.set_max_open(Some(2));
+        manager.add_todo(todo("one", "alice")).await.unwrap();
+
+        let batch = vec![todo("two", "alice"), todo("three", "alice")];
+        assert!(matches!(manager.add_todos(batch).await, Err(TodoError::TooManyTodos(2))));
+        assert_eq!(manager.open_count("alice"), 1);
+        assert_eq!(manager.add_todos(vec![todo("two", "alice")]).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn bulk_tagging_changes_nothing_if_any_todo_is_out_of_reach() {
+        let mut manager = manager();
+        let mine = todo("mine", "alice");
+        let theirs = todo("theirs", "bob");
+        let ids = vec![mine.id.clone(), theirs.id.clone()];
+        manager.add_todos(vec![mine, theirs]).await.unwrap();
+
+        let tag = vec!["urgent".to_string()];
+        let result = manager.bulk_modify_tags(&ids, "alice", &tag, &[]).await;
+        assert!(matches!(result, Err(TodoError::NotFound)));
+        assert!(manager.get_user_todos("alice").await.unwrap().iter().all(|t| t.tags.is_empty()));
+
+        assert_eq!(manager.bulk_modify_tags(&ids[..1], "alice", &tag, &[]).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn positions_skip_archived_and_someday_todos_like_list_does() {
+        let mut manager = manager();
+        let mut archived = todo("archived", "alice");
+        archived.archived = true;
+        let mut parked = todo("parked", "alice");
+        parked.someday = true;
+        let first = todo("first", "alice");
+        let second = todo("second", "alice");
+        let (first_id, second_id) = (first.id.clone(), second.id.clone());
+        // Oldest first under the created sort, so the hidden ones come before
+        for (i, mut todo) in [archived, parked, first, second].into_iter().enumerate() {
+            todo.created_at = Utc::now() - chrono::Duration::hours(10 - i as i64);
+            manager.add_todo(todo).await.unwrap();
+        }
+
+        assert_eq!(manager.resolve_reference("1", "alice", None, SortKey::Created).unwrap(), first_id);
+        assert_eq!(manager.resolve_reference("2", "alice", None, SortKey::Created).unwrap(), second_id);
+        assert!(matches!(manager.resolve_reference("3", "alice", None, SortKey::Created), Err(TodoError::NoSuchPosition(3))));
+    }
+
+    #[test]
+    fn due_sort_compares_zoned_deadlines_as_instants() {
+        let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let mut new_york = todo("new york", "alice");
+        new_york.due_date = Some(at("2026-10-14 09:00"));
+        new_york.due_tz = Some("America/New_York".to_string());
+        let mut tokyo = todo("tokyo", "alice");
+        tokyo.due_date = Some(at("2026-10-14 10:00"));
+        tokyo.due_tz = Some("Asia/Tokyo".to_string());
+
+        // 10:00 in Tokyo is the night before 09:00 in New York
+        let mut todos = vec![&new_york, &tokyo];
+        sort_todos(&mut todos, SortKey::Due);
+        assert_eq!(todos[0].title, "tokyo");
+    }
+
+    #[test]
+    fn a_snoozed_todo_is_blocked_until_the_snooze_ends() {
+        let now = Local::now();
+        let mut snoozed = todo("later", "alice");
+        snoozed.snoozed_until = Some((now + chrono::Duration::hours(1)).with_timezone(&Utc));
+        assert_eq!(snoozed.blocker(now), Some(Blocker::Snoozed));
+        assert!(snoozed.is_actionable(now + chrono::Duration::hours(2)));
+    }
+
+    #[test]
+    fn status_and_priority_accept_aliases_in_any_case_and_spacing() {
+        for input in ["todo", "open", "Pending", " p "] {
+            assert_eq!(Status::from_string(input).unwrap(), Status::Pending, "{input:?}");
+        }
+        for input in ["finished", "Done ", "COMPLETE", "c"] {
+            assert_eq!(Status::from_string(input).unwrap(), Status::Completed, "{input:?}");
+        }
+        for (input, priority) in [("urgent", Priority::High), (" High", Priority::High), ("normal", Priority::Medium), ("MED", Priority::Medium), ("l\t", Priority::Low)] {
+            assert_eq!(Priority::from_string(input).unwrap(), priority, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn unknown_values_are_reported_as_typed() {
+        assert!(matches!(Status::from_string("someday"), Err(TodoError::InvalidStatus(s)) if s == "someday"));
+        assert!(matches!(Priority::from_string(" critical "), Err(TodoError::InvalidPriority(s)) if s == " critical "));
+    }
+
+    #[tokio::test]
+    async fn completing_twice_leaves_the_first_completion_alone() {
+        let mut manager = manager();
+        let first = todo("once", "alice");
+        let id = first.id.clone();
+        manager.add_todo(first).await.unwrap();
+
+        assert!(manager.complete_todo(&id, "alice", Some("done".to_string())).await.unwrap());
+        let completed = manager.get_todo(&id).await.unwrap();
+
+        assert!(!manager.complete_todo(&id, "alice", Some("again".to_string())).await.unwrap());
+        let after = manager.get_todo(&id).await.unwrap();
+        assert_eq!(after.updated_at, completed.updated_at);
+        assert_eq!(after.completed_at, completed.completed_at);
+        assert_eq!(after.completion_note.as_deref(), Some("done"));
+    }
+
+    #[test]
+    fn the_plan_puts_overdue_first_then_priority_then_quick_wins() {
+        let now = Local::now();
+        let planned = |title: &str, priority: Priority, estimate: Option<i64>, due_hours: Option<i64>| {
+            let mut todo = Todo::new(title.to_string(), None, priority, None, "alice".to_string());
+            todo.estimate_minutes = estimate;
+            todo.due_date = due_hours.map(|hours| (now + Duration::hours(hours)).naive_local());
+            todo
+        };
+        let todos = [
+            planned("low", Priority::Low, Some(5), None),
+            planned("high, unestimated", Priority::High, None, None),
+            planned("medium", Priority::Medium, Some(5), None),
+            planned("high, long", Priority::High, Some(60), None),
+            planned("overdue", Priority::Low, None, Some(-2)),
+            planned("high, quick", Priority::High, Some(15), Some(6)),
+        ];
+        let mut plan: Vec<&Todo> = todos.iter().collect();
+        plan_order(&mut plan, now);
+
+        let titles: Vec<&str> = plan.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["overdue", "high, quick", "high, long", "high, unestimated", "medium", "low"]);
+    }
+
+    #[tokio::test]
+    async fn repeating_todos_skip_weekends_when_asked() {
+        let mut manager = manager();
+        manager.set_skip_weekends(true);
+        // Friday 2026-10-16; the next day is a Saturday
+        let friday = NaiveDateTime::parse_from_str("2026-10-16 09:00", "%Y-%m-%d %H:%M").unwrap();
+        let mut daily = todo("stand-up", "alice");
+        daily.due_date = Some(friday);
+        daily.recurrence = Some(Recurrence::Daily);
+        let id = daily.id.clone();
+        manager.add_todo(daily).await.unwrap();
+
+        manager.complete_todo(&id, "alice", None).await.unwrap();
+        let todos = manager.get_user_todos("alice").await.unwrap();
+        let next = todos.iter().find(|t| t.status == Status::Pending).unwrap();
+        assert_eq!(next.due_date, Some(friday + Duration::days(3)));
+    }
+}
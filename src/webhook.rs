@@ -0,0 +1,69 @@
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::config::WebhookConfig;
+use crate::todo::Todo;
+
+/// What happened to a todo, as named in the payload's `event` field.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Added,
+    Completed,
+    Deleted,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: Event,
+    todo: &'a Todo,
+}
+
+/// Posts `{event, todo}` JSON to the configured URL. Delivery is best
+/// effort: failures are reported on stderr and never undo or fail the change
+/// that caused them.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Webhook {
+    /// `None` when no URL is configured.
+    pub fn from_config(config: &WebhookConfig) -> Option<Self> {
+        let url = config.url.as_deref()?.trim();
+        if url.is_empty() {
+            return None;
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .ok()?;
+        Some(Self { client, url: url.to_string() })
+    }
+
+    /// Sends one request per todo, all at once, and waits for them so the
+    /// process doesn't exit with requests still in flight.
+    pub async fn send(&self, event: Event, todos: &[Todo]) {
+        let mut requests = tokio::task::JoinSet::new();
+        for todo in todos {
+            let request = self.client.post(&self.url).json(&Payload { event, todo });
+            requests.spawn(async move { request.send().await?.error_for_status() });
+        }
+
+        let mut failed = 0;
+        let mut first_error = None;
+        while let Some(result) = requests.join_next().await {
+            let error = match result {
+                Ok(Ok(_)) => continue,
+                Ok(Err(error)) => error.to_string(),
+                Err(error) => error.to_string(),
+            };
+            failed += 1;
+            first_error.get_or_insert(error);
+        }
+        if let Some(error) = first_error {
+            eprintln!("Webhook to {} failed for {} of {} events: {}", self.url, failed, todos.len(), error);
+        }
+    }
+}